@@ -3,33 +3,71 @@
 //! This module is used for Minecraft genuine verification.
 //!
 //! Since Mojang has deprecated Mojang account verification method,
-//! this module exclusively supports Microsoft OAuth2.
+//! this module exclusively supports Microsoft OAuth2 for genuine accounts.
+//! Offline accounts and third-party accounts are provided by [`offline`] and [`yggdrasil`] modules.
 
-use crate::constants::{CONFIG_DIRECTORY, PROFILE_FILE_NAME};
-use crate::error_handling::JsonError;
-use crate::file_system;
+pub mod entitlements;
+pub mod loopback;
+pub mod name;
+pub mod offline;
+pub mod player;
+pub mod skin;
+pub mod store;
+pub mod yggdrasil;
+
+use crate::auth::entitlements::Entitlements;
+use crate::auth::player::PlayerCertificates;
+use crate::auth::store::AccountStore;
+use crate::error_handling::{AuthError, XboxError};
 use crate::utils::json_processer;
 use crate::utils::request_handler;
 
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderValue};
-use serde::Serialize;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
+use tokio::time::{self, Instant};
 
 // These constants are URLs that will receive POST (some are GET) request and return response.
-const REQUEST_MICROSOFT_OAUTH2_TOKEN: &str = "https://login.live.com/oauth20_token.srf";
+const MICROSOFT_AUTHORIZE: &str =
+    "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize";
+const REQUEST_MICROSOFT_OAUTH2_TOKEN: &str =
+    "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
+const REQUEST_DEVICE_CODE: &str =
+    "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode";
 const XBOX_AUTHENTICATE: &str = "https://user.auth.xboxlive.com/user/authenticate";
 const XSTS_AUTHORIZE: &str = "https://xsts.auth.xboxlive.com/xsts/authorize";
 const REQUEST_ACCESS_TOKEN: &str =
     "https://api.minecraftservices.com/authentication/login_with_xbox";
-const CHECK_IF_PLAYER_OWNS_GAME: &str = "https://api.minecraftservices.com/entitlements/mcstore";
+const REQUEST_ENTITLEMENTS: &str = "https://api.minecraftservices.com/entitlements/license";
 const REQUEST_UUID_AND_USERNAME: &str = "https://api.minecraftservices.com/minecraft/profile";
 
 // This is the Azure client ID that is used to verify the application.
 const AZURE_CLIENT_ID: &str = "a425ebb8-6195-4be7-9418-e5492c5a4efa";
+const MICROSOFT_SCOPE: &str = "XboxLive.signin offline_access";
+/// The interval added to polling once the server asks us to slow down, as RFC 8628 says.
+const SLOW_DOWN_INTERVAL: u64 = 5;
+/// The username of the accounts that only play the demo and have no profiles.
+const DEMO_USERNAME: &str = "Player";
+/// Tokens are treated as expired a bit earlier, so that they don't expire during a request.
+const EXPIRY_MARGIN: u64 = 300;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum AccountType {
+    #[default]
+    Microsoft,
+    Offline,
+    /// An account of a third-party Yggdrasil-compatible authentication server.
+    Yggdrasil,
+}
 
-#[derive(Default, Serialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct MinecraftProfile {
     /// The Minecraft access token.
@@ -38,79 +76,313 @@ pub struct MinecraftProfile {
     uuid: String,
     /// The username which will display in the game.
     username: String,
+    #[serde(default)]
+    account_type: AccountType,
+    /// The API root of the authentication server of a Yggdrasil account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    yggdrasil_server: Option<String>,
+    /// The metadata returned by the API root of the authentication server,
+    /// which is cached at sign-in so that launching doesn't need to fetch it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    yggdrasil_metadata: Option<String>,
+    /// The client token that is paired with the access token of a Yggdrasil account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client_token: Option<String>,
+    /// The tokens used to refresh the Minecraft access token of a Microsoft account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    microsoft_credentials: Option<MicrosoftCredentials>,
+    /// Whether the account doesn't own the game, so that it can only play the demo.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    demo: bool,
+    /// The key pair used to sign chat messages, which is cached until it should be refreshed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    player_certificates: Option<PlayerCertificates>,
+    /// The Xbox user ID of a Microsoft account, which identifies the player in telemetry
+    /// and chat reports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    xuid: Option<String>,
+}
+
+/// A token along with its expiry.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExpiringToken {
+    pub token: String,
+    /// Seconds since the Unix epoch.
+    pub expires_at: u64,
+}
+
+/// The tokens issued by Microsoft identity platform.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MicrosoftToken {
+    pub access_token: ExpiringToken,
+    /// The token requested by `offline_access` scope, which is used to get new tokens silently.
+    pub refresh_token: String,
+}
+
+/// Every token in the chain of Microsoft login.
+///
+/// Each of them is only requested again once it has expired.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MicrosoftCredentials {
+    pub microsoft_token: MicrosoftToken,
+    pub xbox_token: ExpiringToken,
+    pub xsts_token: ExpiringToken,
+    /// The user hash paired with the XSTS token.
+    pub uhs: String,
+    /// The expiry of the Minecraft access token, in seconds since the Unix epoch.
+    pub minecraft_expires_at: u64,
+}
+
+/// The endpoints of the chain of Microsoft login which are requested while refreshing,
+/// or signing in with a device code.
+///
+/// They are the official ones by default, and can be pointed to another server, e.g. a stand-in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MicrosoftEndpoints {
+    pub device_code: String,
+    pub oauth2_token: String,
+    pub xbox_authenticate: String,
+    pub xsts_authorize: String,
+    pub login_with_xbox: String,
+}
+
+/// The code that the player enters on another device to sign in.
+#[derive(Debug, Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    /// The code the player should enter, e.g. `ABCD1234`.
+    pub user_code: String,
+    /// The page where the player enters the code, i.e. `https://www.microsoft.com/link`.
+    pub verification_uri: String,
+    /// Seconds before the code expires.
+    pub expires_in: u64,
+    /// Seconds to wait between polling.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    /// The instruction provided by Microsoft, which is ready to be displayed.
+    pub message: String,
+}
+
+impl Default for MicrosoftEndpoints {
+    fn default() -> Self {
+        Self {
+            device_code: REQUEST_DEVICE_CODE.to_string(),
+            oauth2_token: REQUEST_MICROSOFT_OAUTH2_TOKEN.to_string(),
+            xbox_authenticate: XBOX_AUTHENTICATE.to_string(),
+            xsts_authorize: XSTS_AUTHORIZE.to_string(),
+            login_with_xbox: REQUEST_ACCESS_TOKEN.to_string(),
+        }
+    }
+}
+
+impl MicrosoftEndpoints {
+    /// Points every endpoint to `root`, keeping the path of the official one,
+    /// e.g. `{root}/xsts/authorize`.
+    pub fn with_root(root: &str) -> Self {
+        let rebase = |url: &str| {
+            // The official endpoints are all `https://host/path`.
+            let path = url.splitn(4, '/').nth(3).unwrap_or_default();
+            format!("{}/{}", root.trim_end_matches('/'), path)
+        };
+
+        Self {
+            device_code: rebase(REQUEST_DEVICE_CODE),
+            oauth2_token: rebase(REQUEST_MICROSOFT_OAUTH2_TOKEN),
+            xbox_authenticate: rebase(XBOX_AUTHENTICATE),
+            xsts_authorize: rebase(XSTS_AUTHORIZE),
+            login_with_xbox: rebase(REQUEST_ACCESS_TOKEN),
+        }
+    }
 }
 
 /// Microsoft authorization code -> Microsoft authorization token
+///
+/// `redirect_uri` and `code_verifier` must be the ones used to request the authorization code,
+/// see [`loopback`] module.
 pub async fn request_microsoft_authorization_token(
-    authorization_code: &'static str,
-) -> Result<String, reqwest::Error> {
-    // The request header.
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        CONTENT_TYPE,
-        HeaderValue::from_static("application/x-www-form-urlencoded"),
-    );
-
+    authorization_code: &str,
+    redirect_uri: &str,
+    code_verifier: &str,
+) -> Result<MicrosoftToken, AuthError> {
     // The load that will be submitted.
     let mut load = HashMap::new();
     load.insert("client_id", AZURE_CLIENT_ID);
     load.insert("code", authorization_code);
     load.insert("grant_type", "authorization_code");
-    load.insert("redirect_uri", "https://login.live.com/oauth20_desktop.srf");
-    load.insert("scope", "XboxLive.signin offline_access");
+    load.insert("redirect_uri", redirect_uri);
+    load.insert("code_verifier", code_verifier);
+    load.insert("scope", MICROSOFT_SCOPE);
 
     // Sends POST request and receive response.
     let response =
-        request_handler::send_post_request(REQUEST_MICROSOFT_OAUTH2_TOKEN, Some(headers), &load)
-            .await?;
+        request_handler::send_form_request(REQUEST_MICROSOFT_OAUTH2_TOKEN, &load).await?;
+    let response = json_processer::parse_from_string(&response)
+        .map_err(|e| AuthError::InternetError(e.to_string()))?;
+
+    if let Some(error) = oauth_error(&response) {
+        return Err(error);
+    }
+
+    microsoft_token_from(&response)
+}
+
+/// Refresh token -> Microsoft authorization token
+///
+/// Returns [`AuthError::LoginRequired`] if the refresh token has been revoked or has expired.
+pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<MicrosoftToken, AuthError> {
+    refresh_microsoft_token_at(REQUEST_MICROSOFT_OAUTH2_TOKEN, refresh_token).await
+}
+
+async fn refresh_microsoft_token_at(
+    url: &str,
+    refresh_token: &str,
+) -> Result<MicrosoftToken, AuthError> {
+    let mut load = HashMap::new();
+    load.insert("client_id", AZURE_CLIENT_ID);
+    load.insert("grant_type", "refresh_token");
+    load.insert("refresh_token", refresh_token);
+    load.insert("scope", MICROSOFT_SCOPE);
+
+    let response = request_handler::send_form_request(url, &load).await?;
+    let response = json_processer::parse_from_string(&response)
+        .map_err(|e| AuthError::InternetError(e.to_string()))?;
+
+    match response["error"].as_str() {
+        None => microsoft_token_from(&response),
+        // The player has changed the password, revoked the consent, or not played for 90 days.
+        Some("invalid_grant" | "interaction_required") => Err(AuthError::LoginRequired(
+            response["error_description"]
+                .as_str()
+                .unwrap_or("The refresh token is no longer valid")
+                .to_string(),
+        )),
+        // We already identified that `error` exists.
+        Some(_) => Err(oauth_error(&response).unwrap()),
+    }
+}
+
+/// Requests a device code, which allows players to sign in on another device.
+///
+/// This is useful when there is no browser, e.g. in a terminal or an SSH session.
+/// Display [`DeviceCode::message`] to the player,
+/// and then call [`poll_device_code_token`] to wait until the player has signed in.
+pub async fn request_device_code() -> Result<DeviceCode, AuthError> {
+    request_device_code_with(&MicrosoftEndpoints::default()).await
+}
+
+/// Same as [`request_device_code`], but requests the code from `endpoints`.
+pub async fn request_device_code_with(
+    endpoints: &MicrosoftEndpoints,
+) -> Result<DeviceCode, AuthError> {
+    let mut load = HashMap::new();
+    load.insert("client_id", AZURE_CLIENT_ID);
+    load.insert("scope", MICROSOFT_SCOPE);
+
+    let response = request_handler::send_form_request(&endpoints.device_code, &load).await?;
+    let response = json_processer::parse_from_string(&response)
+        .map_err(|e| AuthError::InternetError(e.to_string()))?;
+
+    if let Some(error) = oauth_error(&response) {
+        return Err(error);
+    }
 
-    Ok(
-        json_processer::parse_from_string(&response).unwrap()["access_token"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-    )
+    serde_json::from_value(response).map_err(|e| AuthError::InternetError(e.to_string()))
+}
+
+/// Device code -> Microsoft authorization token
+///
+/// Polls the token endpoint until the player has signed in,
+/// declined the request, or the device code has expired.
+pub async fn poll_device_code_token(device_code: &DeviceCode) -> Result<MicrosoftToken, AuthError> {
+    poll_device_code_token_with(device_code, &MicrosoftEndpoints::default()).await
+}
+
+/// Same as [`poll_device_code_token`], but polls the token endpoint of `endpoints`.
+pub async fn poll_device_code_token_with(
+    device_code: &DeviceCode,
+    endpoints: &MicrosoftEndpoints,
+) -> Result<MicrosoftToken, AuthError> {
+    let deadline = Instant::now() + Duration::from_secs(device_code.expires_in);
+    let mut interval = device_code.interval;
+
+    let mut load = HashMap::new();
+    load.insert("client_id", AZURE_CLIENT_ID);
+    load.insert("grant_type", "urn:ietf:params:oauth:grant-type:device_code");
+    load.insert("device_code", &device_code.device_code);
+
+    loop {
+        time::sleep(Duration::from_secs(interval)).await;
+
+        if Instant::now() >= deadline {
+            return Err(AuthError::OAuthError(
+                "The device code has expired, please sign in again".to_string(),
+            ));
+        }
+
+        let response = request_handler::send_form_request(&endpoints.oauth2_token, &load).await?;
+        let response = json_processer::parse_from_string(&response)
+            .map_err(|e| AuthError::InternetError(e.to_string()))?;
+
+        match response["error"].as_str() {
+            None => return microsoft_token_from(&response),
+            // The player hasn't finished signing in yet.
+            Some("authorization_pending") => continue,
+            Some("slow_down") => interval += SLOW_DOWN_INTERVAL,
+            // We already identified that `error` exists.
+            Some(_) => return Err(oauth_error(&response).unwrap()),
+        }
+    }
 }
 
 /// Microsoft authorization token -> Xbox token
 pub async fn request_xbox_authentication_response(
     access_token: &str,
-) -> Result<String, reqwest::Error> {
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+) -> Result<ExpiringToken, AuthError> {
+    request_xbox_authentication_at(XBOX_AUTHENTICATE, access_token).await
+}
 
+async fn request_xbox_authentication_at(
+    url: &str,
+    access_token: &str,
+) -> Result<ExpiringToken, AuthError> {
     let load = json!(
         {
             "Properties": {
                 "AuthMethod": "RPS",
                 "SiteName": "user.auth.xboxlive.com",
-                "RpsTicket": access_token,
+                // Tokens issued by Microsoft identity platform must be prefixed with `d=`.
+                "RpsTicket": format!("d={}", access_token),
             },
             "RelyingParty": "http://auth.xboxlive.com",
             "TokenType": "JWT"
         }
     );
 
-    let response =
-        request_handler::send_post_request(XBOX_AUTHENTICATE, Some(headers), &load).await?;
+    let response = request_xbox_live(url, &load).await?;
 
-    Ok(
-        json_processer::parse_from_string(&response).unwrap()["Token"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-    )
+    Ok(ExpiringToken {
+        token: string_field(&response, "/Token")?,
+        expires_at: xbox_expiry(&response),
+    })
 }
 
 /// Xbox token -> XSTS token, UHS
+///
+/// This is the step where most accounts that can't play are refused,
+/// e.g. the ones without Xbox profiles, see [`XboxError`].
 pub async fn request_xsts_authorization_response(
     xbox_token: &str,
-) -> Result<(String, String), reqwest::Error> {
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+) -> Result<(ExpiringToken, String), AuthError> {
+    request_xsts_authorization_at(XSTS_AUTHORIZE, xbox_token).await
+}
 
+async fn request_xsts_authorization_at(
+    url: &str,
+    xbox_token: &str,
+) -> Result<(ExpiringToken, String), AuthError> {
     let load = json!(
         {
             "Properties": {
@@ -124,17 +396,14 @@ pub async fn request_xsts_authorization_response(
         }
     );
 
-    let response = request_handler::send_post_request(XSTS_AUTHORIZE, Some(headers), &load).await?;
+    let response = request_xbox_live(url, &load).await?;
 
     Ok((
-        json_processer::parse_from_string(&response).unwrap()["Token"]
-            .as_str()
-            .unwrap()
-            .to_string(),
-        json_processer::parse_from_string(&response).unwrap()["DisplayClaims"]["xui"][0]["uhs"]
-            .as_str()
-            .unwrap()
-            .to_string(),
+        ExpiringToken {
+            token: string_field(&response, "/Token")?,
+            expires_at: xbox_expiry(&response),
+        },
+        string_field(&response, "/DisplayClaims/xui/0/uhs")?,
     ))
 }
 
@@ -144,64 +413,448 @@ impl MinecraftProfile {
         &mut self,
         xsts_token: &str,
         uhs: &str,
-    ) -> Result<(), reqwest::Error> {
+    ) -> Result<(), AuthError> {
+        self.request_access_token_at(REQUEST_ACCESS_TOKEN, xsts_token, uhs)
+            .await
+    }
+
+    async fn request_access_token_at(
+        &mut self,
+        url: &str,
+        xsts_token: &str,
+        uhs: &str,
+    ) -> Result<(), AuthError> {
         let load = json!(
             {
                 "identityToken": format!("XBL3.0 x={};{}", uhs, xsts_token)
             }
         );
 
-        let response =
-            request_handler::send_post_request(REQUEST_ACCESS_TOKEN, None, &load).await?;
+        let response = minecraft_services_response(
+            request_handler::send_post_request(url, None, &load).await?,
+        )
+        .await?;
 
-        if let Value::String(access_token) =
-            &json_processer::parse_from_string(&response).unwrap()["access_token"]
-        {
-            self.access_token.clone_from(access_token)
+        self.access_token = string_field(&response, "/access_token")?;
+        // The access token is a JWT, whose payload carries the XUID of the account.
+        self.xuid = jwt_payload(&self.access_token)
+            .and_then(|payload| payload["xuid"].as_str().map(|xuid| xuid.to_string()));
+
+        if let Some(credentials) = &mut self.microsoft_credentials {
+            credentials.minecraft_expires_at =
+                expires_in(response["expires_in"].as_u64().unwrap_or_default());
         }
 
         Ok(())
     }
 
-    pub async fn check_if_player_owns_game(&self) -> Result<bool, reqwest::Error> {
-        let response =
-            request_handler::send_get_request(CHECK_IF_PLAYER_OWNS_GAME, &self.access_token)
-                .await?;
+    /// Minecraft access token -> the products owned by the account
+    pub async fn request_entitlements(&self) -> Result<Entitlements, AuthError> {
+        // The request ID is only used to match the response with the request.
+        let url = format!(
+            "{}?requestId={:032x}",
+            REQUEST_ENTITLEMENTS,
+            rand::random::<u128>()
+        );
+        let response = minecraft_services_response(
+            request_handler::send_get_request(&url, &self.access_token).await?,
+        )
+        .await?;
 
-        if let Value::Array(items) = &json_processer::parse_from_string(&response).unwrap()["items"]
-        {
-            if !items.is_empty() {
-                Ok(true)
-            } else {
-                Ok(false)
-            }
-        } else {
-            Ok(false)
-        }
+        Ok(Entitlements::from_json(&response))
+    }
+
+    /// Checks whether the account owns Java Edition, either by purchasing or by Game Pass.
+    pub async fn check_if_player_owns_game(&self) -> Result<bool, AuthError> {
+        Ok(self
+            .request_entitlements()
+            .await?
+            .java_ownership()
+            .is_some())
     }
 
     /// Minecraft access token -> Minecraft username, Minecraft UUID
-    pub async fn request_uuid_and_username_response(&mut self) -> Result<(), reqwest::Error> {
+    ///
+    /// Returns [`AuthError::ProfileNotFound`] if the player hasn't created a profile yet.
+    pub async fn request_uuid_and_username_response(&mut self) -> Result<(), AuthError> {
         let response =
             request_handler::send_get_request(REQUEST_UUID_AND_USERNAME, &self.access_token)
                 .await?;
 
-        if let (Value::String(username), Value::String(uuid)) = (
-            &json_processer::parse_from_string(&response).unwrap()["name"],
-            &json_processer::parse_from_string(&response).unwrap()["id"],
-        ) {
-            self.username.clone_from(username);
-            self.uuid.clone_from(uuid);
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(AuthError::ProfileNotFound);
         }
 
+        let response = minecraft_services_response(response).await?;
+
+        self.username = string_field(&response, "/name")?;
+        self.uuid = string_field(&response, "/id")?;
+
         Ok(())
     }
 
-    pub async fn save_to_file(&self) -> Result<(), JsonError> {
-        let contents = json_processer::convert_to_string(self)?;
+    /// Microsoft authorization token -> Minecraft profile
+    ///
+    /// Goes through the rest of the chain, i.e. Xbox token, XSTS token and Minecraft access token.
+    /// All the tokens are kept in the profile, so that [`Self::refresh`] can sign in silently later.
+    ///
+    /// If the account doesn't own the game, [`AuthError::GameNotOwned`] is returned,
+    /// unless `allow_demo` is set, which makes a profile that only plays the demo.
+    pub async fn login_with_microsoft_token(
+        token: MicrosoftToken,
+        allow_demo: bool,
+    ) -> Result<Self, AuthError> {
+        let xbox_token = request_xbox_authentication_response(&token.access_token.token).await?;
+        let (xsts_token, uhs) = request_xsts_authorization_response(&xbox_token.token).await?;
 
-        file_system::write_into_file(&CONFIG_DIRECTORY, PROFILE_FILE_NAME, contents.as_bytes())
-            .await
-            .map_err(|e| e.into())
+        let mut profile = Self {
+            microsoft_credentials: Some(MicrosoftCredentials {
+                microsoft_token: token,
+                xbox_token,
+                xsts_token: xsts_token.clone(),
+                uhs: uhs.clone(),
+                minecraft_expires_at: 0,
+            }),
+            ..Default::default()
+        };
+        profile
+            .request_access_token_response(&xsts_token.token, &uhs)
+            .await?;
+
+        if !profile.check_if_player_owns_game().await? {
+            if !allow_demo {
+                return Err(AuthError::GameNotOwned);
+            }
+
+            profile.demo = true;
+        }
+
+        match profile.request_uuid_and_username_response().await {
+            Ok(()) => {}
+            // Accounts that have never owned the game don't have profiles.
+            Err(AuthError::ProfileNotFound) if profile.demo => {
+                profile.username = DEMO_USERNAME.to_string();
+                profile.uuid = offline::offline_uuid(&format!("{}:{}", DEMO_USERNAME, uhs));
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(profile)
+    }
+
+    /// Requests the expired tokens of a Microsoft account again,
+    /// and returns whether the Minecraft access token has been renewed.
+    ///
+    /// Tokens are only requested once they have expired, from the end of the chain to the start.
+    /// If even the Microsoft authorization token has expired, it's refreshed with the refresh token,
+    /// and [`AuthError::LoginRequired`] is returned if the refresh token is no longer valid.
+    pub async fn refresh(&mut self) -> Result<bool, AuthError> {
+        self.refresh_with(&MicrosoftEndpoints::default()).await
+    }
+
+    /// Same as [`Self::refresh`], but requests the tokens from `endpoints`.
+    pub async fn refresh_with(
+        &mut self,
+        endpoints: &MicrosoftEndpoints,
+    ) -> Result<bool, AuthError> {
+        let Some(mut credentials) = self.microsoft_credentials.clone() else {
+            return Ok(false);
+        };
+
+        if !is_expired(credentials.minecraft_expires_at) {
+            return Ok(false);
+        }
+
+        if is_expired(credentials.xsts_token.expires_at) {
+            if is_expired(credentials.xbox_token.expires_at) {
+                if is_expired(credentials.microsoft_token.access_token.expires_at) {
+                    credentials.microsoft_token = refresh_microsoft_token_at(
+                        &endpoints.oauth2_token,
+                        &credentials.microsoft_token.refresh_token,
+                    )
+                    .await?;
+                }
+
+                credentials.xbox_token = request_xbox_authentication_at(
+                    &endpoints.xbox_authenticate,
+                    &credentials.microsoft_token.access_token.token,
+                )
+                .await?;
+            }
+
+            (credentials.xsts_token, credentials.uhs) = request_xsts_authorization_at(
+                &endpoints.xsts_authorize,
+                &credentials.xbox_token.token,
+            )
+            .await?;
+        }
+
+        let (xsts_token, uhs) = (
+            credentials.xsts_token.token.clone(),
+            credentials.uhs.clone(),
+        );
+        self.microsoft_credentials = Some(credentials);
+        self.request_access_token_at(&endpoints.login_with_xbox, &xsts_token, &uhs)
+            .await?;
+
+        Ok(true)
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn account_type(&self) -> AccountType {
+        self.account_type
+    }
+
+    pub fn yggdrasil_server(&self) -> Option<&str> {
+        self.yggdrasil_server.as_deref()
+    }
+
+    pub fn client_token(&self) -> Option<&str> {
+        self.client_token.as_deref()
+    }
+
+    pub fn microsoft_credentials(&self) -> Option<&MicrosoftCredentials> {
+        self.microsoft_credentials.as_ref()
+    }
+
+    /// Whether the account can only play the demo, since it doesn't own the game.
+    pub fn is_demo(&self) -> bool {
+        self.demo
+    }
+
+    /// The Xbox user ID, which only Microsoft accounts have.
+    pub fn xuid(&self) -> Option<&str> {
+        self.xuid.as_deref()
+    }
+
+    /// Saves the profile into the account store as the active account.
+    #[deprecated(note = "profiles are kept in `AccountStore`, use `AccountStore::add` instead")]
+    pub async fn save_to_file(&self) -> Result<(), AuthError> {
+        let mut store = AccountStore::load().await?;
+        store.add(self.clone());
+        store.select(&self.uuid)?;
+
+        store.save().await
+    }
+
+    /// Returns the value of `${user_type}` placeholder in launch arguments.
+    ///
+    /// The versions launched with `minecraftArguments` predate Microsoft accounts,
+    /// and treat `legacy` accounts as the ones which have not been migrated to Mojang accounts,
+    /// so offline accounts are passed as `mojang` accounts on these versions.
+    pub fn user_type(&self, legacy_version: bool) -> &'static str {
+        match (self.account_type, legacy_version) {
+            (AccountType::Microsoft, _) => "msa",
+            (AccountType::Offline, false) => "legacy",
+            (AccountType::Offline, true) => "mojang",
+            (AccountType::Yggdrasil, _) => "mojang",
+        }
+    }
+}
+
+// Tokens must not be printed in logs.
+impl Debug for MinecraftProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MinecraftProfile")
+            .field("access_token", &"..")
+            .field("uuid", &self.uuid)
+            .field("username", &self.username)
+            .field("account_type", &self.account_type)
+            .field("yggdrasil_server", &self.yggdrasil_server)
+            .field(
+                "yggdrasil_metadata",
+                &self.yggdrasil_metadata.as_ref().map(|_| ".."),
+            )
+            .field("client_token", &self.client_token)
+            .field("microsoft_credentials", &self.microsoft_credentials)
+            .field("demo", &self.demo)
+            .field(
+                "player_certificates",
+                &self.player_certificates.as_ref().map(|_| ".."),
+            )
+            .field("xuid", &self.xuid)
+            .finish()
+    }
+}
+
+impl Debug for ExpiringToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExpiringToken")
+            .field("token", &"..")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+impl Debug for MicrosoftToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MicrosoftToken")
+            .field("access_token", &self.access_token)
+            .field("refresh_token", &"..")
+            .finish()
     }
 }
+
+fn default_interval() -> u64 {
+    5
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Converts the lifetime of a token in seconds to its expiry.
+fn expires_in(seconds: u64) -> u64 {
+    unix_now() + seconds
+}
+
+fn is_expired(expires_at: u64) -> bool {
+    expires_at <= unix_now() + EXPIRY_MARGIN
+}
+
+/// Reads a successful token response of Microsoft identity platform.
+fn microsoft_token_from(response: &Value) -> Result<MicrosoftToken, AuthError> {
+    let (Value::String(access_token), Value::String(refresh_token)) =
+        (&response["access_token"], &response["refresh_token"])
+    else {
+        return Err(AuthError::OAuthError(
+            "No access token in the response".to_string(),
+        ));
+    };
+
+    Ok(MicrosoftToken {
+        access_token: ExpiringToken {
+            token: access_token.to_owned(),
+            expires_at: expires_in(response["expires_in"].as_u64().unwrap_or_default()),
+        },
+        refresh_token: refresh_token.to_owned(),
+    })
+}
+
+/// Reads the expiry of an Xbox or XSTS token, e.g. `"NotAfter": "2025-06-15T12:00:00.1234567Z"`.
+///
+/// An unknown expiry is treated as expired, so that the token is simply requested again.
+fn xbox_expiry(response: &Value) -> u64 {
+    unix_time(&response["NotAfter"])
+}
+
+/// Converts an RFC 3339 time to seconds since the Unix epoch, or 0 if it's missing or malformed.
+fn unix_time(time: &Value) -> u64 {
+    time.as_str()
+        .and_then(|time| humantime::parse_rfc3339_weak(time).ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+/// Sends a request to Xbox Live, and decodes the `XErr` code if it's refused.
+async fn request_xbox_live(url: &str, load: &Value) -> Result<Value, AuthError> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+
+    let response = request_handler::send_post_request(url, Some(headers), load).await?;
+    let status = response.status();
+    let body = response.text().await?;
+
+    if status.is_success() {
+        return json_processer::parse_from_string(&body)
+            .map_err(|e| AuthError::InternetError(e.to_string()));
+    }
+
+    // e.g. `{"Identity": "0", "XErr": 2148916233, "Message": "", "Redirect": "..."}`
+    match json_processer::parse_from_string(&body)
+        .ok()
+        .and_then(|error| error["XErr"].as_u64())
+    {
+        Some(code) => Err(AuthError::XboxError(XboxError::from(code))),
+        None => Err(AuthError::InternetError(format!(
+            "Xbox Live responded with {}",
+            status
+        ))),
+    }
+}
+
+/// Reads a response of Minecraft services, which limits how frequently players sign in.
+async fn minecraft_services_response(response: Response) -> Result<Value, AuthError> {
+    let status = response.status();
+
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(AuthError::RateLimited(
+            response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok()),
+        ));
+    }
+
+    let body = response.text().await?;
+
+    if !status.is_success() {
+        let message = json_processer::parse_from_string(&body)
+            .ok()
+            .and_then(|error| {
+                error["errorMessage"]
+                    .as_str()
+                    .or(error["error"].as_str())
+                    .map(|e| e.to_string())
+            })
+            .unwrap_or_default();
+
+        return Err(AuthError::InternetError(format!(
+            "Minecraft services responded with {} {}",
+            status, message
+        )));
+    }
+
+    json_processer::parse_from_string(&body).map_err(|e| AuthError::InternetError(e.to_string()))
+}
+
+/// Decodes the payload of a JWT, whose signature isn't verified here.
+fn jwt_payload(jwt: &str) -> Option<Value> {
+    let payload = jwt.split('.').nth(1)?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+
+    serde_json::from_slice(&payload).ok()
+}
+
+/// Reads a string in a response by a JSON pointer, e.g. `/DisplayClaims/xui/0/uhs`.
+fn string_field(response: &Value, pointer: &str) -> Result<String, AuthError> {
+    response
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(|v| v.to_string())
+        .ok_or_else(|| {
+            AuthError::InternetError(format!("`{}` is missing in the response", pointer))
+        })
+}
+
+/// Reads an OAuth2 error response, e.g. `{"error": "expired_token", "error_description": "..."}`.
+fn oauth_error(response: &Value) -> Option<AuthError> {
+    let error = response["error"].as_str()?;
+
+    Some(AuthError::OAuthError(match error {
+        "authorization_declined" => "The sign-in request was declined".to_string(),
+        "expired_token" => "The device code has expired, please sign in again".to_string(),
+        _ => format!(
+            "{}: {}",
+            error,
+            response["error_description"].as_str().unwrap_or_default()
+        ),
+    }))
+}
@@ -0,0 +1,134 @@
+//! # Entitlements
+//!
+//! Reads the products owned by a Microsoft account from Minecraft services,
+//! and tells whether the account can play Java Edition.
+//!
+//! An account may own Java Edition either by purchasing it,
+//! or by subscribing to Xbox Game Pass for PC or Ultimate.
+//! Other products, e.g. Bedrock Edition or Minecraft Dungeons, don't allow playing Java Edition.
+
+use crate::auth::jwt_payload;
+
+use serde_json::Value;
+
+/// The entitlements that grant Java Edition.
+const JAVA_EDITION: [&str; 2] = ["game_minecraft", "product_minecraft"];
+/// Entitlements of Game Pass are named like `product_game_pass_pc` or `product_game_pass_ultimate`.
+const GAME_PASS_PREFIX: &str = "product_game_pass";
+const GAME_PASS_SOURCE: &str = "GAMEPASS";
+
+/// How an account owns Java Edition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JavaOwnership {
+    Purchase,
+    GamePass,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entitlement {
+    /// e.g. `game_minecraft` or `product_minecraft_bedrock`.
+    pub name: String,
+    /// Where the entitlement comes from, e.g. `PURCHASE` or `GAMEPASS`.
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Entitlements {
+    pub items: Vec<Entitlement>,
+    /// The names of entitlements listed in the signed JWT of the response,
+    /// or `None` if the response isn't signed.
+    signed_names: Option<Vec<String>>,
+}
+
+impl Entitlements {
+    /// Parses the response of `entitlements` endpoint, e.g.
+    ///
+    /// ```json
+    /// {
+    ///     "items": [
+    ///         { "name": "product_minecraft", "source": "PURCHASE", "signature": "..." },
+    ///         { "name": "game_minecraft", "source": "PURCHASE", "signature": "..." }
+    ///     ],
+    ///     "signature": "...",
+    ///     "keyId": "1"
+    /// }
+    /// ```
+    pub fn from_json(response: &Value) -> Self {
+        let items = response["items"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        Some(Entitlement {
+                            name: item["name"].as_str()?.to_string(),
+                            source: item["source"].as_str().map(|s| s.to_string()),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let signed_names = response["signature"].as_str().and_then(signed_names);
+
+        Self {
+            items,
+            signed_names,
+        }
+    }
+
+    /// Returns how the account owns Java Edition, or `None` if it doesn't.
+    ///
+    /// If the response is signed, only the entitlements listed in the signature are trusted.
+    pub fn java_ownership(&self) -> Option<JavaOwnership> {
+        let trusted = self
+            .items
+            .iter()
+            .filter(|item| match &self.signed_names {
+                Some(names) => names.contains(&item.name),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        let game_pass = trusted.iter().any(|item| {
+            item.name.starts_with(GAME_PASS_PREFIX)
+                || (JAVA_EDITION.contains(&item.name.as_str())
+                    && item.source.as_deref() == Some(GAME_PASS_SOURCE))
+        });
+        let java = trusted
+            .iter()
+            .any(|item| JAVA_EDITION.contains(&item.name.as_str()));
+
+        if game_pass {
+            Some(JavaOwnership::GamePass)
+        } else if java {
+            Some(JavaOwnership::Purchase)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the entitlements that have nothing to do with Java Edition,
+    /// e.g. `product_minecraft_bedrock` or `product_dungeons`.
+    pub fn others(&self) -> impl Iterator<Item = &Entitlement> {
+        self.items.iter().filter(|item| {
+            !JAVA_EDITION.contains(&item.name.as_str()) && !item.name.starts_with(GAME_PASS_PREFIX)
+        })
+    }
+}
+
+/// Reads the names of entitlements from the payload of a JWT, e.g.
+/// `{"entitlements": [{"name": "product_minecraft"}], "signerId": "...", ...}`.
+///
+/// The signature itself isn't verified here.
+fn signed_names(jwt: &str) -> Option<Vec<String>> {
+    let payload = jwt_payload(jwt)?;
+
+    Some(
+        payload["entitlements"]
+            .as_array()?
+            .iter()
+            .filter_map(|e| e["name"].as_str().map(|name| name.to_string()))
+            .collect(),
+    )
+}
@@ -0,0 +1,187 @@
+//! # Browser Login
+//!
+//! Signs in with the authorization code flow of Microsoft identity platform.
+//!
+//! A temporary HTTP server is started on `localhost`, which is registered as the redirect URI.
+//! Once the player has signed in within the browser, the browser is redirected to the server
+//! along with the authorization code, so the player doesn't have to copy anything by hand.
+//! The code is protected with [PKCE](https://datatracker.ietf.org/doc/html/rfc7636).
+
+use crate::auth::{self, AZURE_CLIENT_ID, MICROSOFT_AUTHORIZE, MICROSOFT_SCOPE, MicrosoftToken};
+use crate::error_handling::AuthError;
+
+use std::time::Duration;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio::time;
+
+/// RFC 7636 requires the verifier to be 43 to 128 characters long.
+const CODE_VERIFIER_LENGTH: usize = 64;
+const SUCCESS_PAGE: &str = "<!DOCTYPE html><html><body><h1>Signed in successfully</h1>\
+<p>You can close this page and return to the launcher.</p></body></html>";
+const FAILURE_PAGE: &str = "<!DOCTYPE html><html><body><h1>Failed to sign in</h1>\
+<p>Please return to the launcher and try again.</p></body></html>";
+
+/// A pending sign-in, which is waiting for the browser to be redirected back.
+pub struct AuthorizationRequest {
+    /// The page that should be opened in the browser.
+    pub url: String,
+    listener: TcpListener,
+    redirect_uri: String,
+    /// A random value echoed by the redirect, which prevents forged requests.
+    state: String,
+    code_verifier: String,
+}
+
+impl AuthorizationRequest {
+    /// Starts listening on a random port of `localhost`, and builds the authorize URL.
+    pub async fn start() -> Result<Self, AuthError> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| AuthError::InternetError(e.to_string()))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| AuthError::InternetError(e.to_string()))?
+            .port();
+
+        let redirect_uri = format!("http://localhost:{}", port);
+        let state = format!("{:032x}", rand::random::<u128>());
+        let code_verifier = rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(CODE_VERIFIER_LENGTH)
+            .map(char::from)
+            .collect::<String>();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(&code_verifier));
+
+        let url = Url::parse_with_params(
+            MICROSOFT_AUTHORIZE,
+            &[
+                ("client_id", AZURE_CLIENT_ID),
+                ("response_type", "code"),
+                ("redirect_uri", &redirect_uri),
+                ("scope", MICROSOFT_SCOPE),
+                ("state", &state),
+                ("code_challenge", &code_challenge),
+                ("code_challenge_method", "S256"),
+                ("prompt", "select_account"),
+            ],
+        )
+        .map_err(|e| AuthError::OAuthError(e.to_string()))?
+        .to_string();
+
+        Ok(Self {
+            url,
+            listener,
+            redirect_uri,
+            state,
+            code_verifier,
+        })
+    }
+
+    /// Waits until the browser is redirected back, and returns the authorization code.
+    ///
+    /// Requests which don't carry a matching state, e.g. `/favicon.ico`, are ignored.
+    pub async fn wait_for_code(&self, timeout: Duration) -> Result<String, AuthError> {
+        time::timeout(timeout, self.accept_redirect())
+            .await
+            .map_err(|_| {
+                AuthError::OAuthError("Timed out waiting for the player to sign in".to_string())
+            })?
+    }
+
+    /// Authorization code -> Microsoft authorization token
+    ///
+    /// The token can be passed to [`auth::MinecraftProfile::login_with_microsoft_token`] directly.
+    pub async fn wait_for_token(self, timeout: Duration) -> Result<MicrosoftToken, AuthError> {
+        let code = self.wait_for_code(timeout).await?;
+
+        auth::request_microsoft_authorization_token(&code, &self.redirect_uri, &self.code_verifier)
+            .await
+    }
+
+    /// Each connection is read by its own task, so that idle connections,
+    /// e.g. those the browser opens in advance, don't hold up the redirect.
+    async fn accept_redirect(&self) -> Result<String, AuthError> {
+        // The tasks are aborted once the set is dropped.
+        let mut connections = JoinSet::new();
+
+        loop {
+            let (mut stream, url) = tokio::select! {
+                accepted = self.listener.accept() => {
+                    let (mut stream, _) =
+                        accepted.map_err(|e| AuthError::InternetError(e.to_string()))?;
+
+                    connections.spawn(async move {
+                        let url = read_request_url(&mut stream).await;
+                        (stream, url)
+                    });
+                    continue;
+                }
+                Some(Ok(read)) = connections.join_next() => read,
+            };
+
+            let Some(url) = url else {
+                let _ = respond(&mut stream, "400 Bad Request", FAILURE_PAGE).await;
+                continue;
+            };
+
+            let query = |key: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.into_owned())
+            };
+
+            if query("state").as_deref() != Some(self.state.as_str()) {
+                let _ = respond(&mut stream, "404 Not Found", "").await;
+                continue;
+            }
+
+            if let Some(error) = query("error") {
+                let _ = respond(&mut stream, "200 OK", FAILURE_PAGE).await;
+                return Err(AuthError::OAuthError(format!(
+                    "{}: {}",
+                    error,
+                    query("error_description").unwrap_or_default()
+                )));
+            }
+
+            if let Some(code) = query("code") {
+                let _ = respond(&mut stream, "200 OK", SUCCESS_PAGE).await;
+                return Ok(code);
+            }
+
+            let _ = respond(&mut stream, "400 Bad Request", FAILURE_PAGE).await;
+        }
+    }
+}
+
+/// Reads the request line, e.g. `GET /?code=xxx&state=xxx HTTP/1.1`, and returns the URL.
+async fn read_request_url(stream: &mut TcpStream) -> Option<Url> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await.ok()?;
+
+    let target = request_line.split_whitespace().nth(1)?;
+
+    Url::parse(&format!("http://localhost{}", target)).ok()
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,138 @@
+//! # Username Changes
+//!
+//! Changes the username of a Microsoft account through Minecraft services.
+//!
+//! A username can only be changed once every 30 days,
+//! and must not be taken by another account.
+//! Since the username is kept in the account store,
+//! the store must be saved after changing it, so that the next launch uses the new one.
+
+use crate::auth::offline::validate_username;
+use crate::auth::{
+    MinecraftProfile, REQUEST_UUID_AND_USERNAME, minecraft_services_response, string_field,
+};
+use crate::error_handling::AuthError;
+use crate::utils::request_handler;
+
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+
+/// Whether a username can be taken, e.g. `{"status": "AVAILABLE"}`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NameAvailability {
+    Available,
+    /// Another account has taken it.
+    Duplicate,
+    /// It's invalid, or blocked by Mojang.
+    NotAllowed,
+}
+
+/// Whether the account may change its username now, e.g.
+///
+/// ```json
+/// {
+///     "changedAt": "2021-06-01T00:00:00Z",
+///     "createdAt": "2020-12-01T00:00:00Z",
+///     "nameChangeAllowed": false
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NameChangeEligibility {
+    /// When the username was changed last time, in RFC 3339.
+    pub changed_at: Option<String>,
+    /// When the profile was created, in RFC 3339.
+    pub created_at: Option<String>,
+    pub name_change_allowed: bool,
+}
+
+#[derive(Deserialize)]
+struct NameAvailabilityResponse {
+    status: NameAvailability,
+}
+
+impl MinecraftProfile {
+    /// Checks whether a username can be taken by this account.
+    pub async fn check_name_availability(&self, name: &str) -> Result<NameAvailability, AuthError> {
+        self.ensure_microsoft()?;
+
+        if validate_username(name).is_err() {
+            return Ok(NameAvailability::NotAllowed);
+        }
+
+        let response = request_handler::send_get_request(
+            &format!("{}/name/{}/available", REQUEST_UUID_AND_USERNAME, name),
+            &self.access_token,
+        )
+        .await?;
+
+        serde_json::from_value::<NameAvailabilityResponse>(
+            minecraft_services_response(response).await?,
+        )
+        .map(|response| response.status)
+        .map_err(|e| AuthError::InternetError(e.to_string()))
+    }
+
+    /// Checks whether the account may change its username now,
+    /// which is only allowed once every 30 days.
+    pub async fn request_name_change_eligibility(
+        &self,
+    ) -> Result<NameChangeEligibility, AuthError> {
+        self.ensure_microsoft()?;
+
+        let response = request_handler::send_get_request(
+            &format!("{}/namechange", REQUEST_UUID_AND_USERNAME),
+            &self.access_token,
+        )
+        .await?;
+
+        serde_json::from_value(minecraft_services_response(response).await?)
+            .map_err(|e| AuthError::InternetError(e.to_string()))
+    }
+
+    /// Changes the username of the account, and updates it in this profile.
+    ///
+    /// The profile must be saved into the account store afterwards.
+    pub async fn change_name(&mut self, name: &str) -> Result<(), AuthError> {
+        self.change_name_with(name, REQUEST_UUID_AND_USERNAME).await
+    }
+
+    /// Changes the username through the profile endpoint `profile_url` rather than the official one,
+    /// e.g. a stand-in, see [`Self::change_name`].
+    pub async fn change_name_with(
+        &mut self,
+        name: &str,
+        profile_url: &str,
+    ) -> Result<(), AuthError> {
+        self.ensure_microsoft()?;
+        validate_username(name)?;
+
+        let response = request_handler::send_request(
+            Method::PUT,
+            &format!("{}/name/{}", profile_url, name),
+            &self.access_token,
+            None,
+        )
+        .await?;
+
+        match response.status() {
+            StatusCode::BAD_REQUEST => {
+                return Err(AuthError::InvalidUsername(format!(
+                    "`{}` is not allowed by Mojang",
+                    name
+                )));
+            }
+            StatusCode::FORBIDDEN => return Err(AuthError::NameChangeNotAllowed),
+            StatusCode::CONFLICT => return Err(AuthError::NameTaken(name.to_string())),
+            _ => {}
+        }
+
+        // The response is the profile with the new username.
+        let response = minecraft_services_response(response).await?;
+
+        self.username = string_field(&response, "/name")?;
+
+        Ok(())
+    }
+}
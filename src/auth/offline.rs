@@ -0,0 +1,76 @@
+//! # Offline Account
+//!
+//! Offline accounts don't need any verification, so they can be used for LAN games,
+//! servers in offline mode and testing.
+//!
+//! The UUID of an offline account is derived from its username
+//! exactly like what the server does, so the player keeps the same UUID
+//! (and thus the same inventory) on offline servers.
+
+use crate::auth::{AccountType, MinecraftProfile};
+use crate::error_handling::AuthError;
+
+use md5::{Digest, Md5};
+
+const MIN_USERNAME_LENGTH: usize = 3;
+const MAX_USERNAME_LENGTH: usize = 16;
+
+impl MinecraftProfile {
+    /// Creates an offline account.
+    ///
+    /// The username must contain 3 to 16 characters,
+    /// and only letters, digits and underscores are allowed.
+    pub fn new_offline(username: &str) -> Result<Self, AuthError> {
+        validate_username(username)?;
+
+        let uuid = offline_uuid(username);
+
+        Ok(Self {
+            // The game requires an access token, yet nobody will verify it.
+            access_token: uuid.clone(),
+            uuid,
+            username: username.to_string(),
+            account_type: AccountType::Offline,
+            ..Default::default()
+        })
+    }
+}
+
+/// Checks whether a username can be used in the game.
+pub fn validate_username(username: &str) -> Result<(), AuthError> {
+    let length = username.chars().count();
+
+    if !(MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&length) {
+        return Err(AuthError::InvalidUsername(format!(
+            "A username must contain {} to {} characters, but `{}` contains {}",
+            MIN_USERNAME_LENGTH, MAX_USERNAME_LENGTH, username, length
+        )));
+    }
+
+    if let Some(c) = username
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && *c != '_')
+    {
+        return Err(AuthError::InvalidUsername(format!(
+            "A username can only contain letters, digits and underscores, but `{}` contains `{}`",
+            username, c
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the UUID of an offline player, without hyphens.
+///
+/// Like `UUID.nameUUIDFromBytes()` in Java, this is an MD5 name-based (version 3) UUID
+/// of `OfflinePlayer:<username>`.
+pub fn offline_uuid(username: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", username)).into();
+
+    // Set the version to 3.
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    // Set the variant to IETF.
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
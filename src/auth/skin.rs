@@ -0,0 +1,183 @@
+//! # Skins and Capes
+//!
+//! Manages the skins and capes of a Microsoft account through Minecraft services.
+//!
+//! Skins are uploaded as PNG images, which are validated locally before uploading,
+//! while capes can only be chosen from the ones the account owns.
+
+use crate::auth::{
+    AccountType, MinecraftProfile, REQUEST_UUID_AND_USERNAME, minecraft_services_response,
+};
+use crate::error_handling::AuthError;
+use crate::utils::request_handler;
+
+use reqwest::Method;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Modern skins are 64x64, while legacy ones are 64x32.
+const SKIN_SIZES: [(u32, u32); 2] = [(64, 64), (64, 32)];
+
+/// The arm model of a skin.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SkinVariant {
+    /// Arms which are 4 pixels wide, a.k.a. Steve model.
+    Classic,
+    /// Arms which are 3 pixels wide, a.k.a. Alex model.
+    Slim,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Skin {
+    pub id: String,
+    /// `ACTIVE` or `INACTIVE`.
+    pub state: String,
+    pub url: String,
+    pub variant: SkinVariant,
+    /// The name of a default skin, e.g. `STEVE`.
+    pub alias: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Cape {
+    pub id: String,
+    /// `ACTIVE` or `INACTIVE`.
+    pub state: String,
+    pub url: String,
+    /// The name of the cape, e.g. `Migrator`.
+    pub alias: String,
+}
+
+/// The skins and capes of an account.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct Textures {
+    #[serde(default)]
+    pub skins: Vec<Skin>,
+    #[serde(default)]
+    pub capes: Vec<Cape>,
+}
+
+impl MinecraftProfile {
+    /// Lists the skins and capes of the account.
+    pub async fn request_textures(&self) -> Result<Textures, AuthError> {
+        self.ensure_microsoft()?;
+
+        let response =
+            request_handler::send_get_request(REQUEST_UUID_AND_USERNAME, &self.access_token)
+                .await?;
+
+        textures_from(minecraft_services_response(response).await?)
+    }
+
+    /// Uploads a skin, which must be a 64x64 or 64x32 PNG image.
+    pub async fn upload_skin(
+        &self,
+        png: Vec<u8>,
+        variant: SkinVariant,
+    ) -> Result<Textures, AuthError> {
+        self.ensure_microsoft()?;
+        validate_skin(&png)?;
+
+        let variant = match variant {
+            SkinVariant::Classic => "classic",
+            SkinVariant::Slim => "slim",
+        };
+        let file = Part::bytes(png)
+            .file_name("skin.png")
+            .mime_str("image/png")?;
+        let form = Form::new().text("variant", variant).part("file", file);
+
+        let response = request_handler::send_multipart_request(
+            &format!("{}/skins", REQUEST_UUID_AND_USERNAME),
+            &self.access_token,
+            form,
+        )
+        .await?;
+
+        textures_from(minecraft_services_response(response).await?)
+    }
+
+    /// Resets the skin to a default one.
+    pub async fn reset_skin(&self) -> Result<Textures, AuthError> {
+        self.ensure_microsoft()?;
+
+        let response = request_handler::send_request(
+            Method::DELETE,
+            &format!("{}/skins/active", REQUEST_UUID_AND_USERNAME),
+            &self.access_token,
+            None,
+        )
+        .await?;
+
+        textures_from(minecraft_services_response(response).await?)
+    }
+
+    /// Shows a cape owned by the account, see [`Textures::capes`].
+    pub async fn show_cape(&self, cape_id: &str) -> Result<Textures, AuthError> {
+        self.ensure_microsoft()?;
+
+        let response = request_handler::send_request(
+            Method::PUT,
+            &format!("{}/capes/active", REQUEST_UUID_AND_USERNAME),
+            &self.access_token,
+            Some(&json!({ "capeId": cape_id })),
+        )
+        .await?;
+
+        textures_from(minecraft_services_response(response).await?)
+    }
+
+    pub async fn hide_cape(&self) -> Result<Textures, AuthError> {
+        self.ensure_microsoft()?;
+
+        let response = request_handler::send_request(
+            Method::DELETE,
+            &format!("{}/capes/active", REQUEST_UUID_AND_USERNAME),
+            &self.access_token,
+            None,
+        )
+        .await?;
+
+        textures_from(minecraft_services_response(response).await?)
+    }
+
+    /// Only Microsoft accounts have profiles on Minecraft services.
+    pub(crate) fn ensure_microsoft(&self) -> Result<(), AuthError> {
+        match self.account_type {
+            AccountType::Microsoft => Ok(()),
+            _ => Err(AuthError::UnsupportedAccount(format!(
+                "{:?} accounts are not managed by Minecraft services",
+                self.account_type
+            ))),
+        }
+    }
+}
+
+/// Checks whether an image can be used as a skin, and returns its width and height.
+///
+/// Only the header of the PNG is read, so the image itself is not decoded.
+pub fn validate_skin(png: &[u8]) -> Result<(u32, u32), AuthError> {
+    // The signature is followed by the IHDR chunk, which starts with width and height.
+    if png.len() < 24 || png[..8] != PNG_SIGNATURE || &png[12..16] != b"IHDR" {
+        return Err(AuthError::InvalidSkin("Not a PNG image".to_string()));
+    }
+
+    let width = u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+
+    if SKIN_SIZES.contains(&(width, height)) {
+        Ok((width, height))
+    } else {
+        Err(AuthError::InvalidSkin(format!(
+            "A skin must be 64x64 or 64x32, but this one is {}x{}",
+            width, height
+        )))
+    }
+}
+
+fn textures_from(response: serde_json::Value) -> Result<Textures, AuthError> {
+    serde_json::from_value(response).map_err(|e| AuthError::InternetError(e.to_string()))
+}
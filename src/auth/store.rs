@@ -0,0 +1,362 @@
+//! # Account Store
+//!
+//! Keeps every account signed in on this machine in `accounts.json`, keyed by UUID,
+//! so that players sharing a machine can switch between their accounts.
+//!
+//! One of the accounts is active, which is launched unless an instance pins another one.
+//!
+//! Since the file contains access tokens and refresh tokens, it's encrypted with ChaCha20-Poly1305.
+//! The key is either a random key saved in `accounts.key`, which is bound to this machine,
+//! or derived from a passphrase of the player with Argon2id.
+//! Both files are only accessible by the current user.
+
+use crate::auth::MinecraftProfile;
+use crate::constants::{
+    ACCOUNTS_FILE_NAME, ACCOUNTS_KEY_FILE_NAME, CONFIG_DIRECTORY, PROFILE_FILE_NAME,
+};
+use crate::error_handling::{AuthError, JsonError};
+use crate::file_system;
+use crate::utils::json_processer;
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use argon2::Argon2;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AccountStore {
+    /// The UUID of the active account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active: Option<String>,
+    #[serde(default)]
+    accounts: BTreeMap<String, MinecraftProfile>,
+    /// The directory where the store is saved.
+    #[serde(skip)]
+    directory: PathBuf,
+    #[serde(skip)]
+    key: StoreKey,
+}
+
+/// Where the key that encrypts the account store comes from.
+#[derive(Clone, Default)]
+pub enum StoreKey {
+    /// A random key saved in `accounts.key` beside the store.
+    #[default]
+    KeyFile,
+    /// A key derived from a passphrase of the player.
+    Passphrase(String),
+}
+
+/// How `accounts.json` looks like on disk.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EncryptedStore {
+    kdf: Kdf,
+    /// Base64 encoded.
+    nonce: String,
+    /// The encrypted [`AccountStore`], which is base64 encoded.
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+enum Kdf {
+    KeyFile,
+    #[serde(rename_all = "PascalCase")]
+    Argon2id {
+        /// Base64 encoded.
+        salt: String,
+    },
+}
+
+impl AccountStore {
+    /// Reads `accounts.json` in the config directory with the machine-bound key.
+    pub async fn load() -> Result<Self, AuthError> {
+        Self::load_from(&CONFIG_DIRECTORY, StoreKey::KeyFile).await
+    }
+
+    /// Reads `accounts.json` in a directory.
+    ///
+    /// If the file doesn't exist yet, the single `profile.json` saved by older versions
+    /// is imported as the active account.
+    /// Both of it and a store which isn't encrypted yet are encrypted immediately.
+    pub async fn load_from<P: AsRef<Path>>(
+        directory: &P,
+        key: StoreKey,
+    ) -> Result<Self, AuthError> {
+        let directory = directory.as_ref();
+
+        // Whether the store is read from plaintext, which has to be encrypted now.
+        let (mut store, migrated): (Self, bool) =
+            match json_processer::read(&directory, ACCOUNTS_FILE_NAME).await {
+                Ok(contents) if contents.get("Ciphertext").is_some() => {
+                    let encrypted: EncryptedStore =
+                        serde_json::from_value(contents).map_err(JsonError::from)?;
+                    let plaintext = decrypt(directory, &key, &encrypted).await?;
+
+                    (
+                        serde_json::from_slice(&plaintext).map_err(JsonError::from)?,
+                        false,
+                    )
+                }
+                // Saved by older versions without encryption.
+                Ok(contents) => (
+                    serde_json::from_value(contents).map_err(JsonError::from)?,
+                    true,
+                ),
+                Err(JsonError::FileSystemError(e)) if e.kind() == io::ErrorKind::NotFound => {
+                    match json_processer::read(&directory, PROFILE_FILE_NAME).await {
+                        Ok(profile) => {
+                            let mut store = Self::default();
+                            store.add(serde_json::from_value(profile).map_err(JsonError::from)?);
+
+                            (store, true)
+                        }
+                        Err(JsonError::FileSystemError(e))
+                            if e.kind() == io::ErrorKind::NotFound =>
+                        {
+                            (Self::default(), false)
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+        store.directory = directory.to_path_buf();
+        store.key = key;
+
+        if migrated {
+            store.save().await?;
+
+            // The plaintext profile is useless once it has been imported.
+            if let Err(e) = file_system::remove_file(&directory, PROFILE_FILE_NAME).await
+                && e.kind() != io::ErrorKind::NotFound
+            {
+                return Err(JsonError::from(e).into());
+            }
+        }
+
+        Ok(store)
+    }
+
+    /// Encrypts the store, and writes it into `accounts.json` atomically.
+    pub async fn save(&self) -> Result<(), AuthError> {
+        let plaintext = json_processer::convert_to_string(self).map_err(JsonError::from)?;
+        let contents = json_processer::convert_to_string(
+            &encrypt(&self.directory, &self.key, plaintext.as_bytes()).await?,
+        )
+        .map_err(JsonError::from)?;
+
+        file_system::write_private_file(&self.directory, ACCOUNTS_FILE_NAME, contents.as_bytes())
+            .await
+            .map_err(|e| JsonError::from(e).into())
+    }
+
+    /// Changes the key, which takes effect on the next [`Self::save`].
+    pub fn set_key(&mut self, key: StoreKey) {
+        self.key = key;
+    }
+
+    /// Adds an account, or replaces the one with the same UUID, e.g. when signing in again.
+    ///
+    /// The account becomes active if there is no active account yet.
+    pub fn add(&mut self, profile: MinecraftProfile) -> Option<MinecraftProfile> {
+        let uuid = profile.uuid().to_string();
+
+        if self.active.is_none() {
+            self.active = Some(uuid.clone());
+        }
+
+        self.accounts.insert(uuid, profile)
+    }
+
+    /// Removes an account. If it was active, no account will be active afterwards.
+    pub fn remove(&mut self, uuid: &str) -> Option<MinecraftProfile> {
+        if self.active.as_deref() == Some(uuid) {
+            self.active = None;
+        }
+
+        self.accounts.remove(uuid)
+    }
+
+    /// Lists all accounts, ordered by UUID.
+    pub fn list(&self) -> impl Iterator<Item = &MinecraftProfile> {
+        self.accounts.values()
+    }
+
+    pub fn get(&self, uuid: &str) -> Option<&MinecraftProfile> {
+        self.accounts.get(uuid)
+    }
+
+    pub fn get_mut(&mut self, uuid: &str) -> Option<&mut MinecraftProfile> {
+        self.accounts.get_mut(uuid)
+    }
+
+    /// Makes an account active.
+    pub fn select(&mut self, uuid: &str) -> Result<(), AuthError> {
+        if !self.accounts.contains_key(uuid) {
+            return Err(AuthError::AccountNotFound(uuid.to_string()));
+        }
+
+        self.active = Some(uuid.to_string());
+
+        Ok(())
+    }
+
+    pub fn active(&self) -> Option<&MinecraftProfile> {
+        self.active.as_deref().and_then(|uuid| self.get(uuid))
+    }
+
+    /// Returns the UUID of the account to launch with,
+    /// i.e. the account pinned by an instance, or the active account if nothing is pinned.
+    pub fn resolve(&self, pinned: Option<&str>) -> Result<String, AuthError> {
+        match pinned.or(self.active.as_deref()) {
+            Some(uuid) if self.accounts.contains_key(uuid) => Ok(uuid.to_string()),
+            Some(uuid) => Err(AuthError::AccountNotFound(uuid.to_string())),
+            None => Err(AuthError::AccountNotFound(
+                "No account has been selected".to_string(),
+            )),
+        }
+    }
+}
+
+// The passphrase must not be printed in logs.
+impl Debug for StoreKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeyFile => write!(f, "KeyFile"),
+            Self::Passphrase(_) => write!(f, "Passphrase(..)"),
+        }
+    }
+}
+
+async fn encrypt(
+    directory: &Path,
+    key: &StoreKey,
+    plaintext: &[u8],
+) -> Result<EncryptedStore, AuthError> {
+    let (kdf, key) = match key {
+        StoreKey::KeyFile => {
+            let key = match machine_key(directory).await? {
+                Some(key) => key,
+                None => generate_machine_key(directory).await?,
+            };
+
+            (Kdf::KeyFile, key)
+        }
+        StoreKey::Passphrase(passphrase) => {
+            let salt = rand::random::<[u8; SALT_LENGTH]>();
+
+            (
+                Kdf::Argon2id {
+                    salt: STANDARD.encode(salt),
+                },
+                derive_key(passphrase, &salt)?,
+            )
+        }
+    };
+
+    let nonce = rand::random::<[u8; 12]>();
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))?;
+
+    Ok(EncryptedStore {
+        kdf,
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+async fn decrypt(
+    directory: &Path,
+    key: &StoreKey,
+    encrypted: &EncryptedStore,
+) -> Result<Vec<u8>, AuthError> {
+    let key = match (&encrypted.kdf, key) {
+        // A new key could never decrypt the accounts, so it's only generated when saving.
+        (Kdf::KeyFile, StoreKey::KeyFile) => machine_key(directory).await?.ok_or_else(|| {
+            AuthError::EncryptionError(format!("`{}` is missing", ACCOUNTS_KEY_FILE_NAME))
+        })?,
+        (Kdf::Argon2id { salt }, StoreKey::Passphrase(passphrase)) => {
+            derive_key(passphrase, &decode(salt)?)?
+        }
+        (Kdf::KeyFile, StoreKey::Passphrase(_)) => {
+            return Err(AuthError::EncryptionError(
+                "The accounts are not protected by a passphrase".to_string(),
+            ));
+        }
+        (Kdf::Argon2id { .. }, StoreKey::KeyFile) => {
+            return Err(AuthError::EncryptionError(
+                "The accounts are protected by a passphrase".to_string(),
+            ));
+        }
+    };
+
+    let nonce = decode(&encrypted.nonce)?;
+
+    if nonce.len() != 12 {
+        return Err(AuthError::EncryptionError("Invalid nonce".to_string()));
+    }
+
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            decode(&encrypted.ciphertext)?.as_slice(),
+        )
+        .map_err(|_| {
+            AuthError::EncryptionError(
+                "Failed to decrypt the accounts, the key may be wrong".to_string(),
+            )
+        })
+}
+
+/// Reads `accounts.key`, or returns `None` if it doesn't exist.
+async fn machine_key(directory: &Path) -> Result<Option<[u8; KEY_LENGTH]>, AuthError> {
+    match file_system::read_file(&directory, ACCOUNTS_KEY_FILE_NAME).await {
+        Ok(key) => key.try_into().map(Some).map_err(|_| {
+            AuthError::EncryptionError(format!("`{}` is corrupted", ACCOUNTS_KEY_FILE_NAME))
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(JsonError::from(e).into()),
+    }
+}
+
+/// Generates a random key, and saves it into `accounts.key`.
+async fn generate_machine_key(directory: &Path) -> Result<[u8; KEY_LENGTH], AuthError> {
+    let key = rand::random::<[u8; KEY_LENGTH]>();
+
+    file_system::write_private_file(&directory, ACCOUNTS_KEY_FILE_NAME, &key)
+        .await
+        .map_err(JsonError::from)?;
+
+    Ok(key)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LENGTH], AuthError> {
+    let mut key = [0; KEY_LENGTH];
+
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))?;
+
+    Ok(key)
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, AuthError> {
+    STANDARD
+        .decode(text)
+        .map_err(|e| AuthError::EncryptionError(e.to_string()))
+}
@@ -0,0 +1,421 @@
+//! # Yggdrasil Account
+//!
+//! Many communities run their own authentication servers which implement Yggdrasil API,
+//! such as Blessing Skin. Such servers are supported in the game via
+//! [authlib-injector](https://github.com/yushijinhun/authlib-injector),
+//! which is injected into the game as a Java agent.
+
+use crate::auth::{AccountType, MinecraftProfile};
+use crate::constants::{DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::{AuthError, DownloadError};
+use crate::file_system;
+use crate::utils::downloader::{CLIENT, Downloader, FileInfo};
+use crate::utils::json_processer;
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+const AUTHLIB_INJECTOR_OFFICIAL: &str = "https://authlib-injector.yushi.moe";
+const AUTHLIB_INJECTOR_BANGBANG93: &str =
+    "https://bmclapi2.bangbang93.com/mirrors/authlib-injector";
+/// The header which points to the API root if the URL entered by the player is a homepage.
+const API_LOCATION_HEADER: &str = "X-Authlib-Injector-API-Location";
+/// Records the jar downloaded last, so that it can be found and verified without the Internet.
+const INJECTOR_RECORD: &str = "authlib-injector.json";
+
+/// A client that talks to a Yggdrasil-compatible authentication server.
+pub struct YggdrasilClient {
+    /// The API root, e.g. `https://example.com/api/yggdrasil`.
+    api_root: String,
+    /// The metadata returned by the API root, which is passed to authlib-injector.
+    metadata: String,
+}
+
+/// The jar of authlib-injector downloaded last, stored in [`INJECTOR_RECORD`].
+#[derive(Deserialize, Serialize)]
+struct InjectorRecord {
+    file_name: String,
+    sha256: String,
+}
+
+/// A game profile, i.e. a character owned by an account.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct YggdrasilProfile {
+    /// The UUID without hyphens.
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YggdrasilSession {
+    pub access_token: String,
+    pub client_token: String,
+    /// The profiles the player can choose from.
+    ///
+    /// It's absent in responses of refreshing.
+    #[serde(default)]
+    pub available_profiles: Vec<YggdrasilProfile>,
+    /// The profile bound to the access token.
+    ///
+    /// If the account owns more than one profile, the player should choose one of them
+    /// in [`Self::available_profiles`], and bind it via [`YggdrasilClient::refresh`].
+    pub selected_profile: Option<YggdrasilProfile>,
+}
+
+impl YggdrasilClient {
+    /// Connects to an authentication server.
+    ///
+    /// `url` can be either the API root or the homepage of the server,
+    /// as long as the homepage indicates the API root.
+    pub async fn connect(url: &str) -> Result<Self, AuthError> {
+        let url = url.trim_end_matches('/');
+        let response = CLIENT.get(url).send().await?;
+
+        let api_root = match response
+            .headers()
+            .get(API_LOCATION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            Some(location) => response
+                .url()
+                .join(location)
+                .map_err(|e| AuthError::YggdrasilError(e.to_string()))?
+                .to_string()
+                .trim_end_matches('/')
+                .to_string(),
+            None => url.to_string(),
+        };
+
+        let metadata = if api_root == url {
+            response.text().await?
+        } else {
+            CLIENT.get(&api_root).send().await?.text().await?
+        };
+
+        // Make sure this is a Yggdrasil server.
+        match json_processer::parse_from_string(&metadata) {
+            Ok(Value::Object(obj)) if obj.contains_key("meta") => Ok(Self { api_root, metadata }),
+            _ => Err(AuthError::YggdrasilError(format!(
+                "`{}` is not a Yggdrasil API",
+                api_root
+            ))),
+        }
+    }
+
+    pub fn api_root(&self) -> &str {
+        &self.api_root
+    }
+
+    /// Signs in with a username (usually an email) and a password.
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<YggdrasilSession, AuthError> {
+        let load = json!(
+            {
+                "agent": {
+                    "name": "Minecraft",
+                    "version": 1
+                },
+                "username": username,
+                "password": password,
+                "clientToken": format!("{:032x}", rand::random::<u128>()),
+                "requestUser": false
+            }
+        );
+
+        self.post_for_session("authserver/authenticate", &load)
+            .await
+    }
+
+    /// Gets a new access token, and optionally binds a profile to it.
+    ///
+    /// The old access token becomes invalid afterwards.
+    pub async fn refresh(
+        &self,
+        access_token: &str,
+        client_token: &str,
+        selected_profile: Option<&YggdrasilProfile>,
+    ) -> Result<YggdrasilSession, AuthError> {
+        let mut load = json!(
+            {
+                "accessToken": access_token,
+                "clientToken": client_token,
+                "requestUser": false
+            }
+        );
+
+        if let Some(profile) = selected_profile {
+            load["selectedProfile"] = json!({ "id": profile.id, "name": profile.name });
+        }
+
+        self.post_for_session("authserver/refresh", &load).await
+    }
+
+    /// Checks whether an access token is still valid.
+    pub async fn validate(
+        &self,
+        access_token: &str,
+        client_token: &str,
+    ) -> Result<bool, AuthError> {
+        let load = json!({ "accessToken": access_token, "clientToken": client_token });
+
+        let response = CLIENT
+            .post(self.endpoint("authserver/validate"))
+            .json(&load)
+            .send()
+            .await?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT => Ok(true),
+            StatusCode::FORBIDDEN => Ok(false),
+            _ => Err(error_from_response(response).await),
+        }
+    }
+
+    /// Revokes an access token.
+    pub async fn invalidate(
+        &self,
+        access_token: &str,
+        client_token: &str,
+    ) -> Result<(), AuthError> {
+        let load = json!({ "accessToken": access_token, "clientToken": client_token });
+
+        let response = CLIENT
+            .post(self.endpoint("authserver/invalidate"))
+            .json(&load)
+            .send()
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(error_from_response(response).await)
+        }
+    }
+
+    /// Returns the JVM arguments that inject authlib-injector into the game.
+    ///
+    /// The metadata of the server is prefetched,
+    /// so that authlib-injector doesn't have to fetch it again when the game starts.
+    pub fn injector_args<P: AsRef<Path>>(&self, injector_path: &P) -> Vec<String> {
+        vec![
+            format!(
+                "-javaagent:{}={}",
+                injector_path.as_ref().display(),
+                self.api_root
+            ),
+            format!(
+                "-Dauthlibinjector.yggdrasil.prefetched={}",
+                STANDARD.encode(&self.metadata)
+            ),
+        ]
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.api_root, path)
+    }
+
+    async fn post_for_session(
+        &self,
+        path: &str,
+        load: &Value,
+    ) -> Result<YggdrasilSession, AuthError> {
+        let response = CLIENT.post(self.endpoint(path)).json(load).send().await?;
+
+        if response.status().is_success() {
+            Ok(response.json().await?)
+        } else {
+            Err(error_from_response(response).await)
+        }
+    }
+}
+
+impl MinecraftProfile {
+    /// Creates a Yggdrasil account from a session whose profile has been selected.
+    pub fn new_yggdrasil(
+        client: &YggdrasilClient,
+        session: YggdrasilSession,
+    ) -> Result<Self, AuthError> {
+        let profile = session.selected_profile.ok_or_else(|| {
+            AuthError::YggdrasilError("No profile has been selected yet".to_string())
+        })?;
+
+        Ok(Self {
+            access_token: session.access_token,
+            uuid: profile.id,
+            username: profile.name,
+            account_type: AccountType::Yggdrasil,
+            yggdrasil_server: Some(client.api_root.clone()),
+            yggdrasil_metadata: Some(client.metadata.clone()),
+            client_token: Some(session.client_token),
+            ..Default::default()
+        })
+    }
+
+    /// Returns the client of the authentication server of a Yggdrasil account,
+    /// which is built from the metadata cached at sign-in without connecting to the server.
+    pub fn yggdrasil_client(&self) -> Option<YggdrasilClient> {
+        match (&self.yggdrasil_server, &self.yggdrasil_metadata) {
+            (Some(api_root), Some(metadata)) if self.account_type == AccountType::Yggdrasil => {
+                Some(YggdrasilClient {
+                    api_root: api_root.to_owned(),
+                    metadata: metadata.to_owned(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Fetches the metadata of the authentication server of a Yggdrasil account
+    /// which was signed in before the metadata was cached.
+    ///
+    /// Returns whether the metadata is fetched, in which case the profile should be saved.
+    pub async fn cache_yggdrasil_metadata(&mut self) -> Result<bool, AuthError> {
+        let Some(api_root) = &self.yggdrasil_server else {
+            return Ok(false);
+        };
+        if self.account_type != AccountType::Yggdrasil || self.yggdrasil_metadata.is_some() {
+            return Ok(false);
+        }
+
+        let client = YggdrasilClient::connect(api_root).await?;
+        self.yggdrasil_metadata = Some(client.metadata);
+
+        Ok(true)
+    }
+}
+
+/// Downloads the latest authlib-injector, and returns its path.
+///
+/// If it has been downloaded before, this function only checks its integrity.
+pub async fn download_authlib_injector(src: DownloadSource) -> Result<String, DownloadError> {
+    let root = match src {
+        DownloadSource::Official => AUTHLIB_INJECTOR_OFFICIAL,
+        DownloadSource::Bangbang93 => AUTHLIB_INJECTOR_BANGBANG93,
+    };
+
+    let latest = json_processer::parse_from_string(
+        &CLIENT
+            .get(format!("{}/artifact/latest.json", root))
+            .send()
+            .await?
+            .text()
+            .await?,
+    )
+    .map_err(|e| DownloadError::JsonError(e.to_string()))?;
+
+    let (Value::String(version), Value::String(url), Value::String(sha256)) = (
+        &latest["version"],
+        &latest["download_url"],
+        &latest["checksums"]["sha256"],
+    ) else {
+        return Err(DownloadError::OtherError(
+            "Invalid metadata of authlib-injector".to_string(),
+        ));
+    };
+
+    let mut url = url.to_owned();
+
+    if src == DownloadSource::Bangbang93 {
+        url = url.replace(AUTHLIB_INJECTOR_OFFICIAL, AUTHLIB_INJECTOR_BANGBANG93);
+    }
+
+    let file_path = format!("{}/authlib-injector", MINECRAFT_ROOT);
+    let file_name = format!("authlib-injector-{}.jar", version);
+    if !is_injector_file_name(&file_name) {
+        return Err(DownloadError::OtherError(format!(
+            "Invalid version of authlib-injector `{}`",
+            version
+        )));
+    }
+    let jar_path = format!("{}/{}", file_path, file_name);
+
+    if !matches!(sha256_of(&jar_path).await, Ok(hash) if &hash == sha256) {
+        // The metadata only provides SHA256, so integrity is checked after downloading.
+        let file_info = FileInfo {
+            path: Cow::from(Path::new(&file_path)),
+            name: Cow::from(&file_name),
+            url: url.into(),
+            sha1: None,
+        };
+        let downloader = Downloader::new(&CLIENT, file_info);
+        downloader.download_file().await?;
+
+        if &sha256_of(&jar_path).await? != sha256 {
+            return Err(DownloadError::CheckIntegrityError);
+        }
+    }
+
+    let record = InjectorRecord {
+        file_name,
+        sha256: sha256.to_owned(),
+    };
+    let contents = json_processer::convert_to_string(&record)
+        .map_err(|e| DownloadError::JsonError(e.to_string()))?;
+    file_system::write_into_file(&file_path, INJECTOR_RECORD, contents.as_bytes()).await?;
+
+    Ok(jar_path)
+}
+
+/// Returns the path of the authlib-injector downloaded last,
+/// or downloads one from the official source.
+///
+/// The jar is downloaded again if it has been modified or removed since it was downloaded.
+pub async fn authlib_injector_path() -> Result<String, DownloadError> {
+    let file_path = format!("{}/authlib-injector", MINECRAFT_ROOT);
+
+    if let Ok(contents) = file_system::read_file_to_string(&file_path, INJECTOR_RECORD).await
+        && let Ok(record) = serde_json::from_str::<InjectorRecord>(&contents)
+        && is_injector_file_name(&record.file_name)
+    {
+        let jar_path = format!("{}/{}", file_path, record.file_name);
+
+        if matches!(sha256_of(&jar_path).await, Ok(hash) if hash == record.sha256) {
+            return Ok(jar_path);
+        }
+    }
+
+    download_authlib_injector(DownloadSource::Official).await
+}
+
+/// Checks whether a file name is the plain name of an authlib-injector jar,
+/// e.g. `authlib-injector-1.2.5.jar`, which can't point outside the folder it's joined to.
+fn is_injector_file_name(name: &str) -> bool {
+    name.starts_with("authlib-injector-")
+        && name.ends_with(".jar")
+        && !name.contains(['/', '\\'])
+        && !name.contains("..")
+}
+
+async fn sha256_of(path: &str) -> std::io::Result<String> {
+    let contents = fs::read(path).await?;
+
+    Ok(format!("{:x}", Sha256::digest(contents)))
+}
+
+/// Reads the error returned by the server, e.g. `{"errorMessage": "Invalid credentials."}`.
+async fn error_from_response(response: reqwest::Response) -> AuthError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    let message = match json_processer::parse_from_string(&body) {
+        Ok(error) => error["errorMessage"]
+            .as_str()
+            .or(error["error"].as_str())
+            .map(|e| e.to_string()),
+        Err(_) => None,
+    };
+
+    AuthError::YggdrasilError(message.unwrap_or_else(|| status.to_string()))
+}
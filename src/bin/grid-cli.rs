@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand};
+use gridcore::auth::name::NameAvailability;
+use gridcore::auth::store::AccountStore;
+use gridcore::auth::{self, MinecraftProfile};
+use gridcore::error_handling::AuthError;
+
+use clap::{Parser, Subcommand, ValueEnum};
 use tokio::main;
 
 #[derive(Parser)]
@@ -11,8 +16,27 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Login {
+        #[arg(long, value_enum)]
+        account_type: AccountType,
+        /// The username of an offline account.
+        #[arg(long)]
+        username: Option<String>,
+    },
+    /// List all accounts, or switch between them.
+    Accounts {
+        /// Make an account active by its UUID.
+        #[arg(long)]
+        select: Option<String>,
+        /// Remove an account by its UUID.
+        #[arg(long)]
+        remove: Option<String>,
+    },
+    /// Change the username of the active Microsoft account.
+    Rename {
+        name: String,
+        /// Only check whether the username can be taken.
         #[arg(long)]
-        account_type: String,
+        check: bool,
     },
     ListMinecraftVersions,
     DownloadMinecraft,
@@ -24,13 +48,38 @@ enum Commands {
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum AccountType {
+    /// Signs in with a device code, which can be entered in a browser on any device.
+    Microsoft,
+    Offline,
+}
+
 #[main]
 async fn main() {
     let cli = Cli::parse();
 
     match &cli.command {
-        Some(Commands::Login { account_type }) => {
-            println!("Login with account type: {account_type}")
+        Some(Commands::Login {
+            account_type,
+            username,
+        }) => match login(*account_type, username.as_deref()).await {
+            Ok(profile) if profile.is_demo() => println!(
+                "Logged in as {}, who can only play the demo since the game is not owned",
+                profile.username()
+            ),
+            Ok(profile) => println!("Logged in as {}", profile.username()),
+            Err(e) => eprintln!("Failed to log in: {e}"),
+        },
+        Some(Commands::Accounts { select, remove }) => {
+            if let Err(e) = accounts(select.as_deref(), remove.as_deref()).await {
+                eprintln!("Failed to manage accounts: {e}")
+            }
+        }
+        Some(Commands::Rename { name, check }) => {
+            if let Err(e) = rename(name, *check).await {
+                eprintln!("Failed to change the username: {e}")
+            }
         }
         Some(Commands::ListMinecraftVersions) => {
             println!("Minecraft versions are listed below")
@@ -41,9 +90,114 @@ async fn main() {
         Some(Commands::Launch) => {
             println!("Launch Minecraft")
         }
-        Some(Commands::Configs { list }) => {
+        Some(Commands::Configs { list: _ }) => {
             println!("All configurations are listed here")
         }
         _ => eprintln!("Invalid command!"),
     }
 }
+
+async fn login(
+    account_type: AccountType,
+    username: Option<&str>,
+) -> Result<MinecraftProfile, AuthError> {
+    let profile = match account_type {
+        AccountType::Microsoft => {
+            let device_code = auth::request_device_code().await?;
+            println!("{}", device_code.message);
+
+            let token = auth::poll_device_code_token(&device_code).await?;
+            MinecraftProfile::login_with_microsoft_token(token, true).await?
+        }
+        AccountType::Offline => MinecraftProfile::new_offline(username.unwrap_or_default())?,
+    };
+
+    let mut store = AccountStore::load().await?;
+    store.add(profile.clone());
+    store.select(profile.uuid())?;
+    store.save().await?;
+
+    Ok(profile)
+}
+
+async fn accounts(select: Option<&str>, remove: Option<&str>) -> Result<(), AuthError> {
+    let mut store = AccountStore::load().await?;
+
+    if let Some(uuid) = select {
+        store.select(uuid)?;
+    }
+
+    if let Some(uuid) = remove
+        && store.remove(uuid).is_none()
+    {
+        return Err(AuthError::AccountNotFound(uuid.to_string()));
+    }
+
+    store.save().await?;
+
+    let active = store.active().map(|profile| profile.uuid().to_string());
+
+    for profile in store.list() {
+        println!(
+            "{} {} ({}, {:?})",
+            if active.as_deref() == Some(profile.uuid()) {
+                "*"
+            } else {
+                " "
+            },
+            profile.username(),
+            profile.uuid(),
+            profile.account_type()
+        );
+    }
+
+    Ok(())
+}
+
+async fn rename(name: &str, check: bool) -> Result<(), AuthError> {
+    let mut store = AccountStore::load().await?;
+    let uuid = store.resolve(None)?;
+    let refreshed = store
+        .get_mut(&uuid)
+        .ok_or_else(|| AuthError::AccountNotFound(uuid.clone()))?
+        .refresh()
+        .await?;
+
+    if refreshed {
+        store.save().await?;
+    }
+
+    let profile = store
+        .get_mut(&uuid)
+        .ok_or_else(|| AuthError::AccountNotFound(uuid.clone()))?;
+
+    match profile.check_name_availability(name).await? {
+        NameAvailability::Available => {}
+        NameAvailability::Duplicate => return Err(AuthError::NameTaken(name.to_string())),
+        NameAvailability::NotAllowed => {
+            return Err(AuthError::InvalidUsername(format!(
+                "`{}` is not allowed",
+                name
+            )));
+        }
+    }
+
+    if check {
+        println!("{} is available", name);
+        return Ok(());
+    }
+
+    if !profile
+        .request_name_change_eligibility()
+        .await?
+        .name_change_allowed
+    {
+        return Err(AuthError::NameChangeNotAllowed);
+    }
+
+    profile.change_name(name).await?;
+    println!("Changed the username to {}", profile.username());
+
+    // The next launch reads the username from the store.
+    store.save().await
+}
@@ -1,10 +1,141 @@
+//! # Configuration
+//!
+//! Reads and writes `config.toml`, which stores the settings of each instance.
+//!
+//! An instance is identified by the ID of the version it launches, e.g. `1.21.5`.
+
 use crate::constants::{CONFIG_DIRECTORY, CONFIG_FILE_NAME};
+use crate::error_handling::ConfigError;
 use crate::file_system;
 
+use std::collections::{BTreeMap, HashMap};
 use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The client ID of this installation, which is passed to the game as `${clientid}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// Maven repositories searched in order for libraries which don't declare their own,
+    /// before the repositories of Mojang and the mod loaders.
+    pub repositories: Vec<String>,
+    pub instances: HashMap<String, InstanceConfig>,
+    /// The directory where `config.toml` is saved.
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceConfig {
+    /// Commands that wrap the Java process, from the outermost to the innermost.
+    ///
+    /// For example, `[["gamemoderun"], ["nice", "-n", "10"]]` launches the game with
+    /// `gamemoderun nice -n 10 java ...`.
+    pub wrappers: Vec<Vec<String>>,
+    /// Environment variables that are set for the game process,
+    /// which override the inherited ones with the same names.
+    pub env: BTreeMap<String, String>,
+    /// Commands run in order before the game is launched.
+    ///
+    /// If any of them fails, the game won't be launched.
+    pub pre_launch: Vec<Vec<String>>,
+    /// Commands run in order after the game exits.
+    pub post_exit: Vec<Vec<String>>,
+    /// The UUID of the account this instance is always launched with,
+    /// instead of the active account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            repositories: Vec::new(),
+            instances: HashMap::new(),
+            directory: PathBuf::from(CONFIG_DIRECTORY),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `config.toml` in the config directory.
+    ///
+    /// If the file doesn't exist yet, the default configuration is returned.
+    pub async fn load() -> Result<Self, ConfigError> {
+        Self::load_from(&CONFIG_DIRECTORY).await
+    }
+
+    /// Reads `config.toml` in a directory, where it's saved afterwards.
+    pub async fn load_from<P: AsRef<Path>>(directory: &P) -> Result<Self, ConfigError> {
+        let mut config: Self =
+            match file_system::read_file_to_string(directory, CONFIG_FILE_NAME).await {
+                Ok(contents) => toml::from_str(&contents)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Self::default(),
+                Err(e) => return Err(e.into()),
+            };
+        config.directory = directory.as_ref().to_path_buf();
+
+        Ok(config)
+    }
+
+    pub async fn save(&self) -> Result<(), ConfigError> {
+        let contents = toml::to_string_pretty(self)?;
+
+        file_system::write_into_file(&self.directory, CONFIG_FILE_NAME, contents.as_bytes())
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Returns the client ID of this installation, which is generated and saved on first use.
+    ///
+    /// Like the official launcher, it's a random UUID encoded in base64.
+    pub async fn ensure_client_id(&mut self) -> Result<String, ConfigError> {
+        if let Some(client_id) = &self.client_id {
+            return Ok(client_id.clone());
+        }
+
+        let mut uuid = rand::random::<[u8; 16]>();
+        // Set the version to 4.
+        uuid[6] = (uuid[6] & 0x0f) | 0x40;
+        // Set the variant to IETF.
+        uuid[8] = (uuid[8] & 0x3f) | 0x80;
+
+        let uuid = uuid
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        let uuid = format!(
+            "{}-{}-{}-{}-{}",
+            &uuid[..8],
+            &uuid[8..12],
+            &uuid[12..16],
+            &uuid[16..20],
+            &uuid[20..]
+        );
+
+        let client_id = STANDARD.encode(uuid);
+        self.client_id = Some(client_id.clone());
+        self.save().await?;
+
+        Ok(client_id)
+    }
+
+    /// Returns the configuration of an instance, or the default one if it's not configured.
+    pub fn instance(&self, version: &str) -> InstanceConfig {
+        self.instances.get(version).cloned().unwrap_or_default()
+    }
+}
 
 pub async fn create_config_file() -> io::Result<()> {
-    let file = file_system::create_file(&CONFIG_DIRECTORY, CONFIG_FILE_NAME).await?;
+    file_system::create_file(&CONFIG_DIRECTORY, CONFIG_FILE_NAME).await?;
 
     Ok(())
 }
@@ -13,6 +13,8 @@ pub const MINECRAFT_ROOT: &str = "./.minecraft";
 
 pub const CONFIG_DIRECTORY: &str = "./config";
 pub const PROFILE_FILE_NAME: &str = "profile.json";
+pub const ACCOUNTS_FILE_NAME: &str = "accounts.json";
+pub const ACCOUNTS_KEY_FILE_NAME: &str = "accounts.key";
 pub const CONFIG_FILE_NAME: &str = "config.toml";
 
 pub const OFFICIAL: &str = "https://piston-meta.mojang.com";
@@ -57,12 +57,94 @@ pub enum DecompressError {
 pub enum AuthError {
     InternetError(String),
     FileSystemError(String),
+    InvalidUsername(String),
+    YggdrasilError(String),
+    OAuthError(String),
+    /// The refresh token has been revoked or has expired, so the player must sign in again.
+    LoginRequired(String),
+    AccountNotFound(String),
+    EncryptionError(String),
+    /// Xbox Live refused to sign in the account.
+    XboxError(XboxError),
+    /// Minecraft services limit how frequently players sign in.
+    /// It contains how many seconds to wait before retrying, if the server tells.
+    RateLimited(Option<u64>),
+    /// The account doesn't own Minecraft: Java Edition.
+    GameNotOwned,
+    /// The account owns the game, but hasn't created a profile, i.e. chosen a username.
+    ProfileNotFound,
+    /// The operation is not available for this type of account.
+    UnsupportedAccount(String),
+    InvalidSkin(String),
+    /// The username has been taken by another account.
+    NameTaken(String),
+    /// The username was changed less than 30 days ago.
+    NameChangeNotAllowed,
+}
+
+/// The reasons why Xbox Live refuses to authorize an account, identified by `XErr` codes.
+#[derive(Debug, PartialEq)]
+pub enum XboxError {
+    /// The account has been banned from Xbox Live.
+    Banned,
+    /// The Microsoft account doesn't have an Xbox profile yet.
+    NoXboxAccount,
+    /// The account hasn't accepted the terms of Xbox Live.
+    TermsNotAccepted,
+    /// Xbox Live is not available in the country of the account.
+    CountryNotSupported,
+    /// The account must be verified as an adult, which is required in South Korea.
+    AdultVerificationRequired,
+    /// The account belongs to a child, which must be added to a family by an adult.
+    ChildAccount,
+    Other(u64),
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    FileSystemError(io::Error),
+    TomlError(String),
+}
+
+#[derive(Debug)]
+pub enum InstallError {
+    DownloadError(String),
+    FileSystemError(String),
+    /// The installer jar is malformed, or doesn't contain what it declares.
+    InvalidInstaller(String),
+    /// A processor failed, or produced a file that doesn't match the declared hash.
+    ProcessorError(String),
+}
+
+#[derive(Debug)]
+pub enum ModrinthError {
+    InternetError(String),
+    JsonError(String),
+    FileSystemError(String),
+    /// The project or the version doesn't exist.
+    NotFound(String),
+    /// A project has no version for the game version and the loader.
+    NoCompatibleVersion(String),
+    /// A version doesn't support the loader of the instance.
+    MissingLoader(String),
+    /// A version is declared incompatible with another one to be installed, or already installed.
+    Incompatible(String),
+    /// A downloaded file doesn't match its SHA-512.
+    CheckIntegrityError(String),
+    /// The name of a file isn't a plain jar name, so it could be written outside the `mods` folder.
+    InvalidFileName(String),
 }
 
 #[derive(Debug)]
 pub enum LaunchError {
     JsonError(String),
+    DecompressError(String),
     RegexError(String),
+    ProcessError(String),
+    HookError(String),
+    AuthError(String),
+    DownloadError(String),
+    ConfigError(String),
 }
 
 impl Display for JsonError {
@@ -138,6 +220,164 @@ derive_trait!(
     |v| v
 );
 
+impl Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::InternetError(e) => format!("Internet error: {}", e),
+                Self::FileSystemError(e) => format!("Failed to access the profile: {}", e),
+                Self::InvalidUsername(e) => format!("Invalid username: {}", e),
+                Self::YggdrasilError(e) => format!("Authentication server error: {}", e),
+                Self::OAuthError(e) => format!("Microsoft sign-in error: {}", e),
+                Self::LoginRequired(e) => format!("Please sign in again: {}", e),
+                Self::AccountNotFound(e) => format!("Account not found: {}", e),
+                Self::EncryptionError(e) => format!("Failed to protect the accounts: {}", e),
+                Self::XboxError(e) => e.to_string(),
+                Self::RateLimited(Some(seconds)) => format!(
+                    "Too many sign-in attempts, please try again in {} seconds",
+                    seconds
+                ),
+                Self::RateLimited(None) => {
+                    "Too many sign-in attempts, please try again later".to_string()
+                }
+                Self::GameNotOwned =>
+                    "This account doesn't own Minecraft: Java Edition".to_string(),
+                Self::ProfileNotFound => {
+                    "This account doesn't have a Minecraft profile yet, please create one at https://www.minecraft.net/msaprofile".to_string()
+                }
+                Self::UnsupportedAccount(e) => format!("Unsupported account: {}", e),
+                Self::InvalidSkin(e) => format!("Invalid skin: {}", e),
+                Self::NameTaken(e) => format!("The username `{}` has been taken", e),
+                Self::NameChangeNotAllowed =>
+                    "The username can only be changed once every 30 days".to_string(),
+            }
+        )
+    }
+}
+
+impl Error for AuthError {}
+
+impl Display for XboxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Banned => "This account has been banned from Xbox Live".to_string(),
+                Self::NoXboxAccount => {
+                    "This Microsoft account doesn't have an Xbox profile yet, please create one at https://www.xbox.com/live".to_string()
+                }
+                Self::TermsNotAccepted => {
+                    "Please sign in at https://www.xbox.com and accept the terms of Xbox Live"
+                        .to_string()
+                }
+                Self::CountryNotSupported => {
+                    "Xbox Live is not available in the country of this account".to_string()
+                }
+                Self::AdultVerificationRequired => {
+                    "This account must be verified as an adult at https://www.xbox.com".to_string()
+                }
+                Self::ChildAccount => {
+                    "This account belongs to a child, and must be added to a family by an adult at https://account.microsoft.com/family".to_string()
+                }
+                Self::Other(code) => format!("Xbox Live refused to sign in (XErr {})", code),
+            }
+        )
+    }
+}
+
+impl Error for XboxError {}
+
+impl From<u64> for XboxError {
+    fn from(code: u64) -> Self {
+        match code {
+            2148916227 => Self::Banned,
+            2148916233 => Self::NoXboxAccount,
+            2148916234 => Self::TermsNotAccepted,
+            2148916235 => Self::CountryNotSupported,
+            2148916236 | 2148916237 => Self::AdultVerificationRequired,
+            2148916238 => Self::ChildAccount,
+            _ => Self::Other(code),
+        }
+    }
+}
+
+derive_trait!(JsonError, AuthError, AuthError::FileSystemError);
+derive_trait!(reqwest::Error, AuthError, AuthError::InternetError);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::FileSystemError(fse) => fse.to_string(),
+                Self::TomlError(te) => format!("Invalid configuration: {}", te),
+            }
+        )
+    }
+}
+
+impl Error for ConfigError {}
+
+derive_trait!(io::Error, ConfigError, ConfigError::FileSystemError, |v| v);
+derive_trait!(toml::de::Error, ConfigError, ConfigError::TomlError);
+derive_trait!(toml::ser::Error, ConfigError, ConfigError::TomlError);
+
+impl Display for InstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::DownloadError(de) => de.to_owned(),
+                Self::FileSystemError(e) => format!("Failed to write contents to disk: {}", e),
+                Self::InvalidInstaller(e) => format!("Invalid installer: {}", e),
+                Self::ProcessorError(e) => format!("Failed to run processor: {}", e),
+            }
+        )
+    }
+}
+
+impl Error for InstallError {}
+
+derive_trait!(DownloadError, InstallError, InstallError::DownloadError);
+derive_trait!(JsonError, InstallError, InstallError::InvalidInstaller);
+derive_trait!(io::Error, InstallError, InstallError::FileSystemError);
+derive_trait!(
+    result::ZipError,
+    InstallError,
+    InstallError::InvalidInstaller
+);
+
+impl Display for ModrinthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::InternetError(e) => format!("Internet error: {}", e),
+                Self::JsonError(e) => format!("Json parse error: {}", e),
+                Self::FileSystemError(e) => format!("Failed to write contents to disk: {}", e),
+                Self::NotFound(e) => format!("{} is not found on Modrinth", e),
+                Self::NoCompatibleVersion(e) => e.to_owned(),
+                Self::MissingLoader(e) => e.to_owned(),
+                Self::Incompatible(e) => e.to_owned(),
+                Self::CheckIntegrityError(e) => format!("{} is incomplete", e),
+                Self::InvalidFileName(e) => format!("`{}` is not a valid file name of a mod", e),
+            }
+        )
+    }
+}
+
+impl Error for ModrinthError {}
+
+derive_trait!(reqwest::Error, ModrinthError, ModrinthError::InternetError);
+derive_trait!(serde_json::Error, ModrinthError, ModrinthError::JsonError);
+derive_trait!(io::Error, ModrinthError, ModrinthError::FileSystemError);
+
 impl Display for LaunchError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -145,7 +385,13 @@ impl Display for LaunchError {
             "{}",
             match self {
                 Self::JsonError(je) => je.to_string(),
+                Self::DecompressError(de) => format!("Failed to extract native libraries: {}", de),
                 Self::RegexError(re) => re.to_string(),
+                Self::ProcessError(pe) => format!("Failed to run process: {}", pe),
+                Self::HookError(he) => he.to_owned(),
+                Self::AuthError(ae) => ae.to_owned(),
+                Self::DownloadError(de) => de.to_owned(),
+                Self::ConfigError(ce) => ce.to_owned(),
             }
         )
     }
@@ -154,4 +400,9 @@ impl Display for LaunchError {
 impl Error for LaunchError {}
 
 derive_trait!(JsonError, LaunchError, LaunchError::JsonError);
+derive_trait!(DecompressError, LaunchError, LaunchError::DecompressError);
 derive_trait!(regex::Error, LaunchError, LaunchError::RegexError);
+derive_trait!(io::Error, LaunchError, LaunchError::ProcessError);
+derive_trait!(AuthError, LaunchError, LaunchError::AuthError);
+derive_trait!(DownloadError, LaunchError, LaunchError::DownloadError);
+derive_trait!(ConfigError, LaunchError, LaunchError::ConfigError);
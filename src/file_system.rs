@@ -43,6 +43,11 @@ pub async fn open_file<P: AsRef<Path>>(file_path: &P, file_name: &str) -> io::Re
     File::open(file_path.as_ref().join(file_name)).await
 }
 
+/// Returns a stream over the entries within a directory.
+pub async fn read_dir<P: AsRef<Path>>(path: &P) -> io::Result<fs::ReadDir> {
+    fs::read_dir(path).await
+}
+
 /// Writes contents into a file.
 ///
 /// This function will automatically create a file
@@ -58,6 +63,41 @@ pub async fn write_into_file<P: AsRef<Path>>(
     Ok(())
 }
 
+/// Writes contents into a file atomically, and makes it only accessible by the current user.
+///
+/// The contents are written into a temporary file, which is renamed to the target afterwards,
+/// so that the target is never left half-written.
+pub async fn write_private_file<P: AsRef<Path>>(
+    file_path: &P,
+    file_name: &str,
+    contents: &[u8],
+) -> io::Result<()> {
+    create_dir(file_path).await?;
+
+    let target = file_path.as_ref().join(file_name);
+    let temp = file_path.as_ref().join(format!(".{}.tmp", file_name));
+
+    // A temporary file left by a crash may have other permissions.
+    let _ = fs::remove_file(&temp).await;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(&temp).await?;
+    file.write_all(contents).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&temp, &target).await
+}
+
+/// Reads the entire contents of a file into a byte vector.
+pub async fn read_file<P: AsRef<Path>>(file_path: &P, file_name: &str) -> io::Result<Vec<u8>> {
+    fs::read(file_path.as_ref().join(file_name)).await
+}
+
 /// Reads the entire contents of a file into a string.
 pub async fn read_file_to_string<P: AsRef<Path>>(
     file_path: &P,
@@ -1,2 +1,4 @@
+pub mod crash;
 pub mod download;
 pub mod launch;
+pub mod log;
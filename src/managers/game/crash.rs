@@ -0,0 +1,348 @@
+//! # Crash Analysis
+//!
+//! Finds the files left by a crashed game, and tells why it crashed.
+//!
+//! The following files are inspected:
+//!
+//! 1. The newest crash report in `crash-reports`.
+//! 2. The JVM fatal error log, i.e. `hs_err_pid<pid>.log`.
+//! 3. The tail of `logs/latest.log`.
+
+use crate::file_system;
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use regex::Regex;
+use tokio::fs;
+
+/// How many lines at the end of `latest.log` are inspected.
+const LOG_TAIL_LINES: usize = 200;
+/// The maximum number of exceptions collected from a chain.
+const MAX_EXCEPTIONS: usize = 16;
+
+/// Matches the head of a stack trace, e.g. `Caused by: java.lang.RuntimeException: message`.
+static EXCEPTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:Caused by: )?((?:[a-zA-Z_$][\w$]*\.)+[\w$]*(?:Exception|Error|Throwable)[\w$]*)(?::\s*(.*))?$")
+        .unwrap()
+});
+/// Matches `class file version 65.0` in `UnsupportedClassVersionError`.
+static CLASS_VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"class file version (\d+)(?:\.\d+)?|Unsupported major\.minor version (\d+)")
+        .unwrap()
+});
+/// Matches the mod that a failed mixin comes from, e.g. `Mixin [a.mixins.json:A] from mod a`.
+static MIXIN_FROM_MOD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"from mod ([\w-]+)").unwrap());
+/// Matches `Suspected Mod(s):` section of Forge crash reports, e.g. `Example Mod (examplemod)`.
+static SUSPECTED_MOD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Suspected Mods?:\s*\n\s*[^\n(]*\(([\w-]+)\)").unwrap());
+/// Matches the mod that a stack frame belongs to,
+/// e.g. `TRANSFORMER/examplemod@1.0/...` on (Neo)Forge or `~[examplemod-1.0.jar:?]` on Fabric.
+static FRAME_MOD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*at (?:TRANSFORMER/([\w-]+)@|.*[~\[]\[?([\w.+-]+?)(?:-\d[\w.+-]*)?\.jar)")
+        .unwrap()
+});
+/// Matches the problematic frame in a JVM fatal error log.
+static PROBLEMATIC_FRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"# Problematic frame:\s*\n#\s*(.+)").unwrap());
+
+/// Mods and libraries that are part of every game, which are never suspected.
+const NOT_SUSPECTED: &[&str] = &[
+    "minecraft",
+    "client-intermediary",
+    "forge",
+    "neoforge",
+    "fml",
+    "fmlcore",
+    "fmlloader",
+    "javafmllanguage",
+    "modlauncher",
+    "securejarhandler",
+    "fabric-loader",
+    "fabricloader",
+    "quilt-loader",
+    "mixin",
+    "sponge-mixin",
+    "java.base",
+    "lwjgl",
+    "datafixerupper",
+];
+
+#[derive(Debug, Default)]
+pub struct CrashDiagnosis {
+    /// The crash report that was inspected.
+    pub crash_report: Option<PathBuf>,
+    /// The JVM fatal error log that was inspected.
+    pub jvm_crash_log: Option<PathBuf>,
+    /// The exceptions from the outermost one to the root cause,
+    /// e.g. `java.lang.RuntimeException: Mixin transformation failed`.
+    pub exceptions: Vec<String>,
+    /// The ID of the mod that probably caused the crash.
+    pub suspected_mod: Option<String>,
+    pub causes: Vec<KnownCause>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum KnownCause {
+    /// The game or a mod requires a newer (or older) Java.
+    ///
+    /// It contains the required Java version if it is known.
+    WrongJavaVersion(Option<u32>),
+    OutOfMemory,
+    /// The graphics driver doesn't provide the required OpenGL.
+    MissingOpenGl,
+    /// A mixin failed to be applied. It contains the mod the mixin comes from if it is known.
+    MixinApplyFailure(Option<String>),
+    /// The JVM itself crashed. It contains the problematic frame.
+    JvmCrash(String),
+}
+
+impl CrashDiagnosis {
+    /// Returns a human-readable hint that explains the crash.
+    pub fn hint(&self) -> String {
+        let hint = match self.causes.first() {
+            Some(KnownCause::WrongJavaVersion(Some(required))) => format!(
+                "The game was launched with an incompatible Java. Please use Java {} instead.",
+                required
+            ),
+            Some(KnownCause::WrongJavaVersion(None)) => {
+                "The game was launched with an incompatible Java. Please switch to the Java version required by this Minecraft version.".to_string()
+            }
+            Some(KnownCause::OutOfMemory) => {
+                "The game ran out of memory. Please allocate more memory with `-Xmx`, or remove some mods.".to_string()
+            }
+            Some(KnownCause::MissingOpenGl) => {
+                "The graphics driver doesn't support the required OpenGL. Please update your graphics driver.".to_string()
+            }
+            Some(KnownCause::MixinApplyFailure(Some(id))) => format!(
+                "A mixin of mod `{}` failed to be applied. Please update or remove this mod.",
+                id
+            ),
+            Some(KnownCause::MixinApplyFailure(None)) => {
+                "A mixin failed to be applied. Please check whether your mods are compatible with each other.".to_string()
+            }
+            Some(KnownCause::JvmCrash(frame)) => format!(
+                "The Java Virtual Machine crashed at `{}`. Please update your Java and graphics driver.",
+                frame
+            ),
+            None => match self.exceptions.last() {
+                Some(exception) => format!("The game crashed because of `{}`.", exception),
+                None => "The game crashed for an unknown reason.".to_string(),
+            },
+        };
+
+        match (&self.suspected_mod, self.causes.first()) {
+            (Some(id), None | Some(KnownCause::JvmCrash(_))) => {
+                format!("{} Mod `{}` is suspected.", hint, id)
+            }
+            _ => hint,
+        }
+    }
+}
+
+/// Inspects the files left by the game in `game_dir`.
+///
+/// Only the files modified after `since` are inspected, so that the files left by
+/// previous crashes are ignored. Pass [`SystemTime::UNIX_EPOCH`] to inspect all of them.
+pub async fn analyze_crash<P: AsRef<Path>>(
+    game_dir: &P,
+    since: SystemTime,
+) -> io::Result<CrashDiagnosis> {
+    let game_dir = game_dir.as_ref();
+
+    let crash_report = newest_file(&game_dir.join("crash-reports"), since, |name| {
+        name.starts_with("crash-") && name.ends_with(".txt")
+    })
+    .await?;
+    let jvm_crash_log = newest_file(game_dir, since, |name| {
+        name.starts_with("hs_err_pid") && name.ends_with(".log")
+    })
+    .await?;
+
+    let read = async |path: &Option<PathBuf>| match path {
+        Some(path) => fs::read(path)
+            .await
+            .map(|contents| Some(String::from_utf8_lossy(&contents).into_owned())),
+        None => Ok(None),
+    };
+    let crash_report_contents = read(&crash_report).await?;
+    let jvm_crash_log_contents = read(&jvm_crash_log).await?;
+
+    let log = match fs::read(game_dir.join("logs/latest.log")).await {
+        Ok(log) => String::from_utf8_lossy(&log).into_owned(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let lines = log.lines().collect::<Vec<_>>();
+    let log_tail = lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n");
+
+    let mut diagnosis = diagnose(
+        crash_report_contents.as_deref(),
+        jvm_crash_log_contents.as_deref(),
+        &log_tail,
+    );
+    diagnosis.crash_report = crash_report;
+    diagnosis.jvm_crash_log = jvm_crash_log;
+
+    Ok(diagnosis)
+}
+
+/// Tells why the game crashed from the contents of a crash report,
+/// a JVM fatal error log and the tail of the game log.
+pub fn diagnose(
+    crash_report: Option<&str>,
+    jvm_crash_log: Option<&str>,
+    log_tail: &str,
+) -> CrashDiagnosis {
+    let mut diagnosis = CrashDiagnosis::default();
+    let texts = [crash_report, Some(log_tail), jvm_crash_log]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    // The crash report contains the most precise stack trace, so it's preferred.
+    for text in &texts {
+        diagnosis.exceptions = exception_chain(text);
+
+        if !diagnosis.exceptions.is_empty() {
+            break;
+        }
+    }
+
+    diagnosis.suspected_mod = texts.iter().find_map(|text| suspected_mod(text));
+
+    let all = texts.join("\n");
+    let mut add_cause = |cause| {
+        if !diagnosis.causes.contains(&cause) {
+            diagnosis.causes.push(cause);
+        }
+    };
+
+    if let Some(caps) = CLASS_VERSION.captures(&all) {
+        let version = caps
+            .get(1)
+            .or(caps.get(2))
+            .and_then(|v| v.as_str().parse::<u32>().ok());
+        // Class file version 52 is Java 8, and each Java release increases it by one.
+        add_cause(KnownCause::WrongJavaVersion(
+            version.and_then(|v| v.checked_sub(44)),
+        ));
+    } else if all.contains("UnsupportedClassVersionError")
+        || all.contains("cannot be cast to class java.net.URLClassLoader")
+    {
+        // The latter is thrown when old Forge runs on Java 9 or later.
+        add_cause(KnownCause::WrongJavaVersion(None));
+    }
+
+    if all.contains("java.lang.OutOfMemoryError")
+        || all.contains("There is insufficient memory for the Java Runtime Environment")
+        || all.contains("Out of Memory Error")
+    {
+        add_cause(KnownCause::OutOfMemory);
+    }
+
+    if [
+        "GLFW error 65542",
+        "GLFW error 65543",
+        "WGL: The driver does not appear to support OpenGL",
+        "Pixel format not accelerated",
+        "No OpenGL context found in the current thread",
+        "Could not create context",
+    ]
+    .iter()
+    .any(|pattern| all.contains(pattern))
+    {
+        add_cause(KnownCause::MissingOpenGl);
+    }
+
+    if [
+        "MixinApplyError",
+        "Mixin apply failed",
+        "Mixin apply for mod",
+        "InvalidMixinException",
+        "MixinTransformerError",
+        "Mixin transformation of",
+    ]
+    .iter()
+    .any(|pattern| all.contains(pattern))
+    {
+        let id = MIXIN_FROM_MOD
+            .captures(&all)
+            .map(|caps| caps[1].to_string());
+        add_cause(KnownCause::MixinApplyFailure(id));
+    }
+
+    if let Some(caps) = jvm_crash_log.and_then(|log| PROBLEMATIC_FRAME.captures(log)) {
+        add_cause(KnownCause::JvmCrash(caps[1].trim().to_string()));
+    }
+
+    diagnosis
+}
+
+/// Returns the newest file in `dir` whose name satisfies `filter`, and modified after `since`.
+async fn newest_file(
+    dir: &Path,
+    since: SystemTime,
+    filter: impl Fn(&str) -> bool,
+) -> io::Result<Option<PathBuf>> {
+    if fs::metadata(dir).await.is_err() {
+        return Ok(None);
+    }
+
+    let mut newest = None;
+    let mut entries = file_system::read_dir(&dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        if !entry.file_name().to_str().is_some_and(&filter) {
+            continue;
+        }
+
+        let modified = entry.metadata().await?.modified()?;
+
+        if modified >= since && newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+
+    Ok(newest.map(|(_, path)| path))
+}
+
+/// Collects the first exception and its causes.
+fn exception_chain(text: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+
+    for line in text.lines().map(|line| line.trim()) {
+        if (chain.is_empty() || line.starts_with("Caused by: "))
+            && let Some(caps) = EXCEPTION.captures(line)
+        {
+            chain.push(match caps.get(2) {
+                Some(message) => format!("{}: {}", &caps[1], message.as_str()),
+                None => caps[1].to_string(),
+            });
+        }
+
+        if chain.len() >= MAX_EXCEPTIONS {
+            break;
+        }
+    }
+
+    chain
+}
+
+fn suspected_mod(text: &str) -> Option<String> {
+    if let Some(caps) = SUSPECTED_MOD.captures(text) {
+        return Some(caps[1].to_string());
+    }
+
+    if let Some(caps) = MIXIN_FROM_MOD.captures(text) {
+        return Some(caps[1].to_string());
+    }
+
+    text.lines()
+        .filter_map(|line| FRAME_MOD.captures(line))
+        .filter_map(|caps| caps.get(1).or(caps.get(2)).map(|m| m.as_str().to_string()))
+        .find(|id| !NOT_SUSPECTED.contains(&id.as_str()))
+}
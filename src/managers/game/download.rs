@@ -3,6 +3,7 @@ mod version_manifest;
 
 use crate::constants::{Category, DownloadSource};
 use crate::error_handling::DownloadError;
+use crate::managers::version;
 
 use std::num::NonZero;
 use std::thread;
@@ -35,9 +36,20 @@ impl MinecraftDownloader {
         }
     }
 
+    /// Downloads a version.
+    ///
+    /// A version that inherits from another one, e.g. a version installed by a mod loader installer,
+    /// is downloaded along with all of its ancestors.
     pub async fn download_minecraft(&self) -> Result<(), DownloadError> {
-        version_manifest::download_specific_version_manifest(self.version, self.source).await?;
-        mc_file::download_files(self.version, self.source, self.category).await?;
+        let mut ver = self.version.to_owned();
+
+        while let Some(parent) = version::inherits_from(&ver).await? {
+            mc_file::download_libraries(&ver, self.source).await?;
+            ver = parent;
+        }
+
+        version_manifest::download_specific_version_manifest(&ver, self.source).await?;
+        mc_file::download_files(&ver, self.source, self.category).await?;
 
         Ok(())
     }
@@ -30,3 +30,14 @@ pub async fn download_files(
 
     Ok(())
 }
+
+/// Downloads the libraries of a version that inherits from another one,
+/// whose other files are downloaded with the parent version.
+pub async fn download_libraries(ver: &str, src: DownloadSource) -> Result<(), DownloadError> {
+    let manifest_path = format!("{}/versions/{}", MINECRAFT_ROOT, ver);
+    let manifest_name = format!("{}.json", ver);
+
+    let data = json_processer::read(&manifest_path, &manifest_name).await?;
+
+    libraries::download_libraries(&data, src).await
+}
@@ -1,6 +1,8 @@
+use crate::config::Config;
 use crate::constants::{BANGBANG93, DownloadSource, MINECRAFT_ROOT};
 use crate::error_handling::DownloadError;
 use crate::utils::downloader::{CLIENT, Downloader, FileInfo};
+use crate::utils::maven::{self, DEFAULT_REPOSITORIES, MavenCoordinate, ResolvedArtifact};
 
 use std::borrow::Cow;
 use std::env::consts::OS;
@@ -13,6 +15,8 @@ pub(super) async fn download_libraries(
     src: DownloadSource,
 ) -> Result<(), DownloadError> {
     let mut files = Vec::new();
+    // Only loaded when a library doesn't declare its repository.
+    let mut repositories = None;
 
     if let Value::Array(libs) = &data["libraries"] {
         for lib in libs {
@@ -53,6 +57,20 @@ pub(super) async fn download_libraries(
                     };
 
                     files.push(file_info);
+                } else if lib["downloads"].is_null()
+                    && let Value::String(name) = &lib["name"]
+                {
+                    // Libraries of mod loaders are often only declared by Maven coordinates.
+                    if repositories.is_none() {
+                        repositories = Some(load_repositories().await?);
+                    }
+
+                    if let Some(file_info) =
+                        resolve_library(name, lib, src, repositories.as_deref().unwrap_or_default())
+                            .await?
+                    {
+                        files.push(file_info);
+                    }
                 }
                 if let (Value::String(path), Value::String(sha1), Value::String(url)) = (
                     &lib["downloads"]["classifiers"]
@@ -119,3 +137,86 @@ pub(super) async fn download_libraries(
 
     Ok(())
 }
+
+/// Resolves a library which is only declared by its Maven coordinate, e.g.
+/// `{"name": "net.fabricmc:fabric-loader:0.16.14", "url": "https://maven.fabricmc.net/"}`,
+/// against its repository, or the repositories searched for it if it doesn't declare one.
+///
+/// Libraries without known hashes are only downloaded when they're missing,
+/// and libraries found in no repository are fine if they're in place,
+/// since installers extract some of them, e.g. the universal jar of Forge.
+async fn resolve_library(
+    name: &str,
+    lib: &Value,
+    src: DownloadSource,
+    repositories: &[String],
+) -> Result<Option<FileInfo<'static>>, DownloadError> {
+    let Some(coordinate) = MavenCoordinate::parse(name) else {
+        return Err(DownloadError::JsonError(format!(
+            "Invalid Maven coordinate `{}`",
+            name
+        )));
+    };
+
+    let file_path = PathBuf::from(format!(
+        "{}/libraries/{}",
+        MINECRAFT_ROOT,
+        coordinate.directory()
+    ));
+    let file_name = coordinate.file_name();
+    let exists = file_path.join(&file_name).exists();
+
+    let resolved = match &lib["url"] {
+        Value::String(repository) => {
+            let url = coordinate.url(repository);
+            let sha1 = match &lib["sha1"] {
+                Value::String(sha1) => Some(sha1.to_owned()),
+                _ => maven::request_sha1(&url).await?,
+            };
+
+            Some(ResolvedArtifact { url, sha1 })
+        }
+        _ => maven::resolve(&coordinate, repositories)
+            .await
+            .map(|resolved| ResolvedArtifact {
+                sha1: lib["sha1"]
+                    .as_str()
+                    .map(|sha1| sha1.to_owned())
+                    .or(resolved.sha1),
+                ..resolved
+            }),
+    };
+
+    match resolved {
+        Some(ResolvedArtifact { url, sha1 }) if sha1.is_some() || !exists => Ok(Some(FileInfo {
+            path: Cow::from(file_path),
+            name: Cow::from(file_name),
+            url: maven::mirror_url(&url, src).into(),
+            sha1: sha1.map(Cow::from),
+        })),
+        _ if exists => Ok(None),
+        _ => Err(DownloadError::OtherError(format!(
+            "Library `{}` is not found in any repository",
+            name
+        ))),
+    }
+}
+
+/// Returns the repositories in `config.toml`, followed by the default ones.
+async fn load_repositories() -> Result<Vec<String>, DownloadError> {
+    let config = Config::load()
+        .await
+        .map_err(|e| DownloadError::OtherError(e.to_string()))?;
+    let mut repositories = config.repositories;
+
+    for repository in DEFAULT_REPOSITORIES {
+        if repositories
+            .iter()
+            .all(|r| r.trim_end_matches('/') != repository.trim_end_matches('/'))
+        {
+            repositories.push(repository.to_string());
+        }
+    }
+
+    Ok(repositories)
+}
@@ -1,16 +1,48 @@
-use crate::constants::{CONFIG_DIRECTORY, MINECRAFT_ROOT, PROFILE_FILE_NAME};
+use crate::auth::store::{AccountStore, StoreKey};
+use crate::auth::yggdrasil;
+use crate::config::{Config, InstanceConfig};
+use crate::constants::{CONFIG_DIRECTORY, MINECRAFT_ROOT};
 use crate::error_handling::LaunchError;
-use crate::utils::json_processer;
+use crate::file_system;
+use crate::managers::game::crash::{self, CrashDiagnosis};
+use crate::managers::game::log::{LogParser, LogRecord};
+use crate::managers::version;
+use crate::utils::decompresser;
+use crate::utils::maven::MavenCoordinate;
 
 use std::collections::HashMap;
 use std::env::consts::{ARCH, OS};
-use std::process::Command;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::SystemTime;
 
-use regex::Regex;
+use regex::{Captures, Regex};
 use serde::Serialize;
 use serde_json::Value;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc::UnboundedSender;
 
-const CONFIG_NUMS: usize = 18;
+const CONFIG_NUMS: usize = 24;
+const LAUNCHER_NAME: &str = env!("CARGO_PKG_NAME");
+const LAUNCHER_VERSION: &str = env!("CARGO_PKG_VERSION");
+const CLASSPATH_SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
+/// The JVM arguments of the versions which only declare `minecraftArguments`,
+/// which are the same as the ones the official launcher passes to them.
+const LEGACY_JVM_ARGS: [&str; 5] = [
+    "-Djava.library.path=${natives_directory}",
+    "-Dminecraft.launcher.brand=${launcher_name}",
+    "-Dminecraft.launcher.version=${launcher_version}",
+    "-cp",
+    "${classpath}",
+];
+/// The file in the natives directory of a version which lists the jars extracted into it.
+const EXTRACTED_NATIVES: &str = ".extracted";
+/// The port of a server whose address doesn't specify one.
+const DEFAULT_SERVER_PORT: &str = "25565";
+/// The file in which the game records the worlds and servers that are joined via Quick Play.
+const QUICK_PLAY_LOG: &str = "quickPlay/java/gridcore.json";
 
 #[derive(Default, Serialize)]
 pub struct LaunchArguments {
@@ -21,145 +53,717 @@ pub struct LaunchArguments {
 /// Sets the resolution of the game window.
 ///
 /// In general, you're no need to set this manually.
+#[derive(Clone, Copy)]
 pub struct Resolution(pub u16, pub u16);
 
-pub async fn generate_launch_args(
-    version: &str,
-    jvm_x_args: &str,
-) -> Result<Vec<String>, LaunchError> {
-    let manifest_path = format!("{}/versions/{}", MINECRAFT_ROOT, version);
-    let manifest_name = format!("{}.json", version);
+/// The destination the game directly joins once it is launched.
+#[derive(Clone)]
+pub enum QuickPlay {
+    /// The folder name of a world in `saves`.
+    Singleplayer(String),
+    /// The address of a server, optionally followed by a port, e.g. `mc.example.com:25565`.
+    ///
+    /// IPv6 addresses are enclosed in brackets to be followed by a port, e.g. `[::1]:25565`.
+    Multiplayer(String),
+    /// The ID of a Realm.
+    Realms(String),
+}
 
-    let data = json_processer::read(&manifest_path, &manifest_name).await?;
-    let mut launch_args = LaunchArguments::default();
+/// Events that happen during a game session.
+#[derive(Debug)]
+pub enum LaunchEvent {
+    /// A line printed by a hook command.
+    HookOutput {
+        hook: String,
+        line: String,
+    },
+    HookExited {
+        hook: String,
+        status: ExitStatus,
+    },
+    /// A post-exit hook couldn't be run, e.g. its program doesn't exist.
+    HookFailed {
+        hook: String,
+        error: String,
+    },
+    /// A record printed to the standard output by the game.
+    GameLog(LogRecord),
+    /// A line printed to the standard error by the game.
+    GameError(String),
+    GameExited(ExitStatus),
+    /// The game exited abnormally, and the files it left were analyzed.
+    GameCrashed(CrashDiagnosis),
+    /// The game exited abnormally, but the files it left couldn't be analyzed.
+    CrashAnalysisFailed(String),
+}
 
-    // Get original launch arguments from JSON.
-    if let Value::Object(args) = &data["arguments"] {
-        for (arg_ty, arg_val) in args {
-            match arg_ty.as_str() {
-                "jvm" => {
-                    if let Value::Array(jvm_args) = &arg_val[arg_ty] {
-                        jvm_args.iter().for_each(|arg| {
-                            if arg.is_string() {
-                                // We already identified that `arg` is a string.
-                                launch_args.jvm.push(arg.as_str().unwrap().to_string())
-                            } else if arg.is_object() {
-                                let name = &arg.as_object().unwrap()["rules"][0]["os"]["name"];
-                                let arch = &arg.as_object().unwrap()["rules"][0]["os"]["arch"];
-
-                                if [Value::Null, Value::String(OS.replace("macos", "osx"))]
-                                    .contains(name)
-                                    || [Value::Null, Value::String(ARCH.into())].contains(arch)
-                                {
-                                    let value = &arg.as_object().unwrap()["value"];
-
-                                    if value.is_string() {
-                                        launch_args.jvm.push(value.as_str().unwrap().to_string());
-                                    } else if value.is_array() {
-                                        launch_args.jvm.extend(
-                                            value
-                                                .as_array()
-                                                .unwrap()
-                                                .iter()
-                                                .map(|arg| arg.as_str().unwrap().to_string()),
-                                        );
-                                    }
-                                }
-                            }
-                        });
-                    }
-                }
-                "game" => {
-                    if let Value::Array(game_args) = &arg_val[arg_ty] {
-                        game_args.iter().for_each(|arg| {
-                            if arg.is_string() {
-                                // We already identified that `arg` is a string.
-                                launch_args.game.push(arg.as_str().unwrap().to_string())
+/// Options that change how the game window behaves and where the game starts.
+#[derive(Clone, Default)]
+pub struct LaunchOptions {
+    pub resolution: Option<Resolution>,
+    pub fullscreen: bool,
+    /// Launches the game in demo mode, which is usually used when the player doesn't own the game.
+    pub demo: bool,
+    pub quick_play: Option<QuickPlay>,
+    /// The key of the account store, which only needs to be set if it's protected by a passphrase.
+    pub store_key: StoreKey,
+}
+
+impl LaunchArguments {
+    /// Collects the arguments declared in a version JSON.
+    ///
+    /// Arguments guarded by rules are only collected if the rules are satisfied
+    /// by current platform and the features enabled in `options`.
+    /// Placeholders such as `${auth_player_name}` are kept as they are.
+    pub fn from_json(data: &Value, options: &LaunchOptions) -> Self {
+        let features = options.features();
+        let mut launch_args = Self::default();
+
+        if let Value::Object(args) = &data["arguments"] {
+            for (arg_ty, arg_val) in args {
+                let target = match arg_ty.as_str() {
+                    "jvm" => &mut launch_args.jvm,
+                    "game" => &mut launch_args.game,
+                    // According to JSON file, other cases will never reach.
+                    _ => unreachable!(),
+                };
+
+                if let Value::Array(arr) = arg_val {
+                    arr.iter().for_each(|arg| match arg {
+                        Value::String(arg) => target.push(arg.to_owned()),
+                        Value::Object(obj) if rules_allow(&obj["rules"], &features) => {
+                            match &obj["value"] {
+                                Value::String(value) => target.push(value.to_owned()),
+                                Value::Array(values) => target.extend(
+                                    values
+                                        .iter()
+                                        .filter_map(|v| v.as_str().map(|v| v.to_owned())),
+                                ),
+                                _ => {}
                             }
-                            // We deliberately omitted the cases when `arg` is an object,
-                            // because they can be configured later.
-                        });
-                    }
+                        }
+                        _ => {}
+                    });
                 }
-                // According to JSON file, other cases will never reach.
-                _ => unreachable!(),
             }
+
+            // Quick Play was introduced in 23w14a.
+            // Multiplayer servers can still be joined directly on the versions before it.
+            if !data["arguments"]["game"]
+                .to_string()
+                .contains("is_quick_play_multiplayer")
+            {
+                launch_args.game.extend(options.legacy_server_args());
+            }
+        } else if let Value::String(args) = &data["minecraftArguments"] {
+            launch_args.game = args
+                .split(' ')
+                .map(|a| a.to_string())
+                .collect::<Vec<String>>();
+
+            // LWJGL 2 must run on the main thread on macOS.
+            if cfg!(target_os = "macos") {
+                launch_args.jvm.push("-XstartOnFirstThread".to_string());
+            }
+            launch_args
+                .jvm
+                .extend(LEGACY_JVM_ARGS.map(|arg| arg.to_string()));
+
+            // Old versions don't have feature rules,
+            // so the corresponding arguments must be appended manually.
+            if options.demo {
+                launch_args.game.push("--demo".to_string());
+            }
+            if let Some(Resolution(width, height)) = options.resolution {
+                launch_args.game.extend([
+                    "--width".to_string(),
+                    width.to_string(),
+                    "--height".to_string(),
+                    height.to_string(),
+                ]);
+            }
+            launch_args.game.extend(options.legacy_server_args());
+        }
+
+        // No version declares this argument with a rule.
+        if options.fullscreen {
+            launch_args.game.push("--fullscreen".to_string());
         }
-    } else if let Value::String(args) = &data["minecraftArguments"] {
-        launch_args.game = args
-            .split(' ')
-            .map(|a| a.to_string())
-            .collect::<Vec<String>>();
+
+        launch_args
+    }
+
+    /// Replaces the placeholders in the JVM arguments of a version, e.g. `${classpath}`.
+    ///
+    /// Mod loaders like Forge refer to `${version_name}` in JVM arguments as well,
+    /// which is the file name of the game jar on the classpath.
+    pub fn replace_jvm_placeholders(
+        &mut self,
+        data: &Value,
+        version: &str,
+    ) -> Result<(), regex::Error> {
+        let mut jvm_args = HashMap::new();
+        jvm_args.insert("natives_directory", natives_directory(version));
+        jvm_args.insert("classpath", classpath(data).join(CLASSPATH_SEPARATOR));
+        jvm_args.insert("launcher_name", LAUNCHER_NAME.to_string());
+        jvm_args.insert("launcher_version", LAUNCHER_VERSION.to_string());
+        jvm_args.insert("version_name", version.to_string());
+        // Modern Forge puts some libraries on the module path with these.
+        jvm_args.insert("library_directory", format!("{}/libraries", MINECRAFT_ROOT));
+        jvm_args.insert("classpath_separator", CLASSPATH_SEPARATOR.to_string());
+
+        replace_placeholders(&mut self.jvm, &jvm_args)
     }
+}
 
-    let logging_arg = data["logging"]["client"]["argument"]
-        .as_str()
-        // We already identified that `arg` is a string.
-        .unwrap()
-        .to_owned();
+impl LaunchOptions {
+    /// Returns the features that are referred by rules in version JSONs.
+    fn features(&self) -> HashMap<&'static str, bool> {
+        HashMap::from([
+            ("is_demo_user", self.demo),
+            ("has_custom_resolution", self.resolution.is_some()),
+            ("has_quick_plays_support", self.quick_play.is_some()),
+            (
+                "is_quick_play_singleplayer",
+                matches!(self.quick_play, Some(QuickPlay::Singleplayer(_))),
+            ),
+            (
+                "is_quick_play_multiplayer",
+                matches!(self.quick_play, Some(QuickPlay::Multiplayer(_))),
+            ),
+            (
+                "is_quick_play_realms",
+                matches!(self.quick_play, Some(QuickPlay::Realms(_))),
+            ),
+        ])
+    }
+
+    /// Returns the values of placeholders that are related to these options.
+    fn placeholders(&self) -> HashMap<&'static str, String> {
+        let mut placeholders = HashMap::new();
+
+        if let Some(Resolution(width, height)) = self.resolution {
+            placeholders.insert("resolution_width", width.to_string());
+            placeholders.insert("resolution_height", height.to_string());
+        }
+
+        if let Some(quick_play) = &self.quick_play {
+            placeholders.insert("quickPlayPath", QUICK_PLAY_LOG.to_string());
+
+            let (key, value) = match quick_play {
+                QuickPlay::Singleplayer(world) => ("quickPlaySingleplayer", world),
+                QuickPlay::Multiplayer(server) => ("quickPlayMultiplayer", server),
+                QuickPlay::Realms(id) => ("quickPlayRealms", id),
+            };
+            placeholders.insert(key, value.to_owned());
+        }
+
+        placeholders
+    }
+
+    /// Returns `--server` and `--port` arguments for versions that don't support Quick Play.
+    fn legacy_server_args(&self) -> Vec<String> {
+        match &self.quick_play {
+            Some(QuickPlay::Multiplayer(server)) => {
+                let (host, port) = split_server_address(server);
+
+                vec![
+                    "--server".to_string(),
+                    host.to_string(),
+                    "--port".to_string(),
+                    port.to_string(),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub async fn generate_launch_args(
+    version: &str,
+    jvm_x_args: &str,
+    options: &LaunchOptions,
+) -> Result<Vec<String>, LaunchError> {
+    // Versions of mod loaders are merged with the vanilla versions they inherit from.
+    let data = version::read_version_json(version).await?;
+    // Broken JSONs are refused before anything else is done.
+    let main_class = string_field(&data, "mainClass", version)?;
+
+    // The game is launched with the account pinned by the instance, or the active account.
+    let mut config = Config::load().await?;
+    let mut store = AccountStore::load_from(&CONFIG_DIRECTORY, options.store_key.clone()).await?;
+    let uuid = store.resolve(config.instance(version).account.as_deref())?;
+    // We already identified that the account exists.
+    let profile = store.get_mut(&uuid).unwrap();
+
+    // Sign in silently if the access token has expired.
+    let refreshed = profile.refresh().await?;
+    let cached = profile.cache_yggdrasil_metadata().await?;
+    if refreshed || cached {
+        store.save().await?;
+    }
+
+    // We already identified that the account exists.
+    let profile = store.get(&uuid).unwrap();
+
+    // Accounts that don't own the game can only play the demo.
+    let options = &LaunchOptions {
+        demo: options.demo || profile.is_demo(),
+        ..options.clone()
+    };
+
+    // Get original launch arguments from JSON.
+    let mut launch_args = LaunchArguments::from_json(&data, options);
+
+    // The logging configuration makes the game print Log4j XML events,
+    // which are parsed by `LogParser` later.
+    // Versions before 1.7 don't have this configuration.
+    let mut logging_arg = Vec::new();
+
+    if let (Value::String(arg), Value::String(id)) = (
+        &data["logging"]["client"]["argument"],
+        &data["logging"]["client"]["file"]["id"],
+    ) {
+        logging_arg.push(arg.to_owned());
+        replace_placeholders(
+            &mut logging_arg,
+            &HashMap::from([(
+                "path",
+                format!("{}/assets/log_configs/{}", MINECRAFT_ROOT, id),
+            )]),
+        )?;
+    }
 
     // Replace placeholders with actual arguments.
     // We first handle jvm arguments.
+    extract_natives(&data, &natives_directory(version)).await?;
+    copy_game_jar(&data, version).await?;
+    launch_args.replace_jvm_placeholders(&data, version)?;
 
     // Then handle game arguments.
-    let profile = json_processer::read(&CONFIG_DIRECTORY, PROFILE_FILE_NAME).await?;
-
     let mut game_args = HashMap::with_capacity(CONFIG_NUMS);
-    game_args.insert(
-        "username",
-        String::from(profile["username"].as_str().unwrap()),
-    );
+    game_args.insert("auth_player_name", profile.username().to_string());
     game_args.insert("version_name", version.to_string());
     game_args.insert("game_directory", MINECRAFT_ROOT.to_string());
     game_args.insert("assets_root", format!("{}/assets", MINECRAFT_ROOT));
+    game_args.insert("assets_index_name", string_field(&data, "assets", version)?);
+    game_args.insert("auth_uuid", profile.uuid().to_string());
+    game_args.insert("auth_access_token", profile.access_token().to_string());
+    game_args.insert("clientid", config.ensure_client_id().await?);
+    // Accounts other than Microsoft ones don't have XUIDs.
+    game_args.insert("auth_xuid", profile.xuid().unwrap_or("0").to_string());
     game_args.insert(
-        "assets_index_name",
-        data["assets"].as_str().unwrap().to_string(),
-    );
-    game_args.insert("auth_uuid", String::from(profile["uuid"].as_str().unwrap()));
-    game_args.insert(
-        "auth_access_token",
-        String::from(profile["accessToken"].as_str().unwrap()),
-    );
-    game_args.insert(
-        "clientid",
-        String::from("MTFjMTBkZjctMmJlMC00ZTZmLTgxMDItMWYzODMxZDU4NDk0"),
+        "user_type",
+        profile
+            .user_type(data["minecraftArguments"].is_string())
+            .to_string(),
     );
-    game_args.insert("auth_xuid", String::from("2535472045104657"));
-    game_args.insert("user_type", String::from("msa"));
-    game_args.insert("version_type", data["type"].as_str().unwrap().to_string());
+    game_args.insert("version_type", string_field(&data, "type", version)?);
+    game_args.extend(options.placeholders());
 
-    let re = Regex::new(r"^\$\{(.*?)}$")?;
+    replace_placeholders(&mut launch_args.game, &game_args)?;
 
-    for arg in launch_args.game.iter_mut() {
-        match game_args.get(&re.captures(arg).unwrap()[1]) {
-            Some(a) => {
-                a.clone_into(arg);
-            }
-            None => continue,
-        }
-    }
+    // The metadata of the authentication server is cached in the profile.
+    if let Some(client) = profile.yggdrasil_client() {
+        let injector_path = yggdrasil::authlib_injector_path().await?;
 
-    // We already identified that `arg` is a string.
-    let log_arg = data["mainClass"].as_str().unwrap().to_owned();
+        // Java agents must be loaded before anything else.
+        launch_args
+            .jvm
+            .splice(0..0, client.injector_args(&injector_path));
+    }
 
     // We finally merge these parts of arguments into one vector.
     Ok(launch_args
         .jvm
         .into_iter()
         .chain(jvm_x_args.split(' ').map(|arg| arg.to_owned()))
-        .chain([logging_arg])
+        .chain(logging_arg)
+        .chain([main_class])
         .chain(launch_args.game)
         .collect())
 }
 
-pub fn launch_game(args: Vec<String>) {
-    let output = Command::new("java").args(args).output().unwrap();
+/// Returns the classpath of a version, i.e. its libraries allowed on this platform,
+/// followed by the game jar.
+///
+/// Libraries which only pack native libraries are left out, since they're extracted instead.
+/// The game jar is always named after the version, even if it's inherited from the parent,
+/// since mod loaders like Forge find it by `${version_name}`, see [`copy_game_jar`].
+pub fn classpath(data: &Value) -> Vec<String> {
+    let mut classpath = Vec::new();
 
-    if !output.status.success() {
-        println!(
-            "Failed to launch Minecraft: {}",
-            String::from_utf8_lossy(&output.stderr)
+    if let Value::Array(libs) = &data["libraries"] {
+        for lib in libs {
+            if !rules_allow(&lib["rules"], &HashMap::new()) {
+                continue;
+            }
+
+            let path = match (&lib["downloads"], &lib["name"]) {
+                (Value::Null, Value::String(name)) => {
+                    MavenCoordinate::parse(name).map(|coordinate| coordinate.path())
+                }
+                (downloads, _) => downloads["artifact"]["path"]
+                    .as_str()
+                    .map(|path| path.to_owned()),
+            };
+
+            // A library may be declared by both a mod loader and the game.
+            if let Some(path) = path.map(|path| format!("{}/libraries/{}", MINECRAFT_ROOT, path))
+                && !classpath.contains(&path)
+            {
+                classpath.push(path);
+            }
+        }
+    }
+
+    let id = data["id"].as_str().unwrap_or_default();
+    classpath.push(format!("{}/versions/{}/{}.jar", MINECRAFT_ROOT, id, id));
+
+    classpath
+}
+
+/// Copies the game jar a version inherits to `versions/<id>/<id>.jar`, as the official launcher does.
+///
+/// The copy is only made once, so it's refreshed by removing it.
+async fn copy_game_jar(data: &Value, version: &str) -> Result<(), LaunchError> {
+    let Value::String(jar) = &data["jar"] else {
+        return Ok(());
+    };
+    let target = PathBuf::from(format!(
+        "{}/versions/{}/{}.jar",
+        MINECRAFT_ROOT, version, version
+    ));
+
+    if jar != version && !fs::try_exists(&target).await? {
+        fs::copy(
+            format!("{}/versions/{}/{}.jar", MINECRAFT_ROOT, jar, jar),
+            &target,
         )
+        .await?;
     }
+
+    Ok(())
+}
+
+/// Returns the directory into which the native libraries of a version are extracted.
+fn natives_directory(version: &str) -> String {
+    format!("{}/versions/{}/natives", MINECRAFT_ROOT, version)
+}
+
+/// Builds the command that launches the game with the wrappers and environment variables of an instance.
+pub fn game_command(args: Vec<String>, instance: &InstanceConfig) -> Command {
+    let mut program = instance
+        .wrappers
+        .iter()
+        .flatten()
+        .cloned()
+        .chain(["java".to_string()])
+        .chain(args);
+
+    // There is always at least `java` in the chain.
+    let mut command = Command::new(program.next().unwrap());
+    command.args(program).envs(&instance.env);
+
+    command
+}
+
+/// Launches the game and waits until it exits.
+///
+/// The pre-launch hooks of the instance are run in order before the game is launched,
+/// and the launch is aborted once any of them fails.
+/// After the game exits, the post-exit hooks are run with its exit code and the path of its log,
+/// which are passed via `${exit_code}` and `${log_path}` placeholders in their arguments,
+/// as well as `GRIDCORE_EXIT_CODE` and `GRIDCORE_LOG_PATH` environment variables.
+///
+/// The output of both hooks and the game is sent to `events` line by line.
+/// If the game exits abnormally, the files it left are analyzed and the diagnosis is sent as well.
+pub async fn launch_game(
+    args: Vec<String>,
+    instance: &InstanceConfig,
+    events: UnboundedSender<LaunchEvent>,
+) -> Result<ExitStatus, LaunchError> {
+    for hook in &instance.pre_launch {
+        let status = run_hook(hook, &HashMap::new(), &events).await?;
+
+        if !status.success() {
+            return Err(LaunchError::HookError(format!(
+                "Pre-launch hook `{}` failed with {}",
+                hook.join(" "),
+                status
+            )));
+        }
+    }
+
+    let launched_at = SystemTime::now();
+    let mut child = tokio::process::Command::from(game_command(args, instance))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (_, _, status) = tokio::join!(
+        forward_logs(child.stdout.take(), &events),
+        forward_lines(child.stderr.take(), &events, LaunchEvent::GameError),
+        child.wait(),
+    );
+    let status = status?;
+
+    let _ = events.send(LaunchEvent::GameExited(status));
+
+    // The game has already exited, so a failed analysis mustn't lose its status.
+    if !status.success() {
+        let _ = events.send(
+            match crash::analyze_crash(&MINECRAFT_ROOT, launched_at).await {
+                Ok(diagnosis) => LaunchEvent::GameCrashed(diagnosis),
+                Err(e) => LaunchEvent::CrashAnalysisFailed(e.to_string()),
+            },
+        );
+    }
+
+    let exit_code = status.code().map(|c| c.to_string()).unwrap_or_default();
+    let vars = HashMap::from([
+        ("exit_code", exit_code),
+        ("log_path", format!("{}/logs/latest.log", MINECRAFT_ROOT)),
+    ]);
+
+    // The game has already exited, so failed post-exit hooks are only reported via `events`,
+    // and the remaining ones are still run.
+    for hook in &instance.post_exit {
+        if let Err(e) = run_hook(hook, &vars, &events).await {
+            let _ = events.send(LaunchEvent::HookFailed {
+                hook: hook.join(" "),
+                error: e.to_string(),
+            });
+        }
+    }
+
+    Ok(status)
+}
+
+/// Extracts the native libraries packed in the jars of a version into `directory`,
+/// which versions before 1.19 load via `${natives_directory}`.
+///
+/// The extracted jars are recorded in the directory, so that they're skipped by later launches.
+async fn extract_natives(data: &Value, directory: &str) -> Result<(), LaunchError> {
+    let os = OS.replace("macos", "osx");
+    let extracted = match file_system::read_file_to_string(&directory, EXTRACTED_NATIVES).await {
+        Ok(extracted) => extracted,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut record = extracted.clone();
+
+    if let Value::Array(libs) = &data["libraries"] {
+        for lib in libs {
+            if !rules_allow(&lib["rules"], &HashMap::new()) {
+                continue;
+            }
+
+            if let Some(path) = natives_path(lib, &os)
+                && !record.lines().any(|line| line == path)
+                && let Some((file_path, file_name)) = path.rsplit_once('/')
+            {
+                decompresser::decompress_file(
+                    &format!("{}/libraries/{}", MINECRAFT_ROOT, file_path),
+                    file_name,
+                    &directory.to_string(),
+                )
+                .await?;
+
+                record.push_str(path);
+                record.push('\n');
+            }
+        }
+    }
+
+    if record != extracted {
+        file_system::write_into_file(&directory, EXTRACTED_NATIVES, record.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the path of the jar which packs the native libraries of a library on `os`,
+/// which is named as in version JSONs, e.g. `osx`.
+///
+/// The classifier of the jar is looked up in `natives`, since it differs between libraries,
+/// e.g. `natives-osx` for LWJGL 2 and `natives-macos` for LWJGL 3.
+pub fn natives_path<'a>(lib: &'a Value, os: &str) -> Option<&'a str> {
+    let classifier = lib["natives"][os].as_str()?.replace(
+        "${arch}",
+        if cfg!(target_pointer_width = "64") {
+            "64"
+        } else {
+            "32"
+        },
+    );
+
+    lib["downloads"]["classifiers"][&classifier]["path"].as_str()
+}
+
+/// Runs a hook command, and sends its output to `events`.
+///
+/// `vars` are both substituted into the arguments and set as environment variables
+/// prefixed with `GRIDCORE_`.
+async fn run_hook(
+    hook: &[String],
+    vars: &HashMap<&str, String>,
+    events: &UnboundedSender<LaunchEvent>,
+) -> Result<ExitStatus, LaunchError> {
+    let mut hook = hook.to_vec();
+    replace_placeholders(&mut hook, vars)?;
+
+    let Some((program, args)) = hook.split_first() else {
+        return Ok(ExitStatus::default());
+    };
+    let name = hook.join(" ");
+
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .envs(
+            vars.iter()
+                .map(|(k, v)| (format!("GRIDCORE_{}", k.to_uppercase()), v)),
+        )
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let hook_output = |line| LaunchEvent::HookOutput {
+        hook: name.clone(),
+        line,
+    };
+    let (_, _, status) = tokio::join!(
+        forward_lines(child.stdout.take(), events, hook_output),
+        forward_lines(child.stderr.take(), events, hook_output),
+        child.wait(),
+    );
+    let status = status?;
+
+    let _ = events.send(LaunchEvent::HookExited {
+        hook: name.clone(),
+        status,
+    });
+
+    Ok(status)
+}
+
+/// Reads lines from the output of a process, and sends them as events.
+async fn forward_lines<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    events: &UnboundedSender<LaunchEvent>,
+    event: impl Fn(String) -> LaunchEvent,
+) {
+    if let Some(reader) = reader {
+        let mut lines = BufReader::new(reader).lines();
+
+        // A closed receiver only means nobody is listening, so the output is still drained.
+        while let Ok(Some(line)) = lines.next_line().await {
+            let _ = events.send(event(line));
+        }
+    }
+}
+
+/// Reads the standard output of the game, and sends the parsed records as events.
+async fn forward_logs<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    events: &UnboundedSender<LaunchEvent>,
+) {
+    if let Some(reader) = reader {
+        let mut lines = BufReader::new(reader).lines();
+        let mut parser = LogParser::new();
+
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(record) = parser.feed(&line) {
+                let _ = events.send(LaunchEvent::GameLog(record));
+            }
+        }
+
+        if let Some(record) = parser.finish() {
+            let _ = events.send(LaunchEvent::GameLog(record));
+        }
+    }
+}
+
+/// Splits the address of a server into the host and the port, which is 25565 if it's omitted.
+///
+/// IPv6 addresses must be enclosed in brackets to be followed by a port, e.g. `[::1]:25566`,
+/// so an address with more than one colon and no brackets is a bare IPv6 address.
+fn split_server_address(server: &str) -> (&str, &str) {
+    let (host, port) = match server
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+    {
+        Some((host, port)) => (host, port.strip_prefix(':')),
+        None => match server.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (server, None),
+        },
+    };
+
+    (
+        host,
+        port.filter(|port| !port.is_empty())
+            .unwrap_or(DEFAULT_SERVER_PORT),
+    )
+}
+
+/// Reads a string field of a version JSON, which is required to launch the version.
+fn string_field(data: &Value, key: &str, version: &str) -> Result<String, LaunchError> {
+    data[key].as_str().map(|v| v.to_owned()).ok_or_else(|| {
+        LaunchError::JsonError(format!(
+            "`{}` is missing in the JSON of version `{}`",
+            key, version
+        ))
+    })
+}
+
+/// Replaces placeholders like `${version_name}` in arguments.
+///
+/// Unknown placeholders are kept as they are.
+fn replace_placeholders(
+    args: &mut [String],
+    values: &HashMap<&str, String>,
+) -> Result<(), regex::Error> {
+    let re = Regex::new(r"\$\{(.*?)}")?;
+
+    for arg in args.iter_mut() {
+        *arg = re
+            .replace_all(arg, |caps: &Captures| match values.get(&caps[1]) {
+                Some(a) => a.to_owned(),
+                None => caps[0].to_owned(),
+            })
+            .into_owned();
+    }
+
+    Ok(())
+}
+
+/// Checks whether the rules of an argument or a library are satisfied.
+///
+/// An item without rules is always allowed.
+/// Otherwise, the action of the last matched rule decides whether the item is allowed.
+fn rules_allow(rules: &Value, features: &HashMap<&str, bool>) -> bool {
+    let Value::Array(rules) = rules else {
+        return true;
+    };
+
+    let mut allowed = false;
+
+    for rule in rules {
+        let os_matched = [Value::Null, Value::String(OS.replace("macos", "osx"))]
+            .contains(&rule["os"]["name"])
+            && [Value::Null, Value::String(ARCH.into())].contains(&rule["os"]["arch"]);
+        let features_matched = match &rule["features"] {
+            Value::Object(required) => required.iter().all(|(feature, expected)| {
+                expected.as_bool() == Some(*features.get(feature.as_str()).unwrap_or(&false))
+            }),
+            _ => true,
+        };
+
+        if os_matched && features_matched {
+            allowed = rule["action"] == "allow";
+        }
+    }
+
+    allowed
 }
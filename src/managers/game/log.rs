@@ -0,0 +1,202 @@
+//! # Game Log
+//!
+//! Converts the standard output of the game into structured records.
+//!
+//! With the logging configuration declared in its version JSON,
+//! a modern client prints each log event as a Log4j XML element, e.g.
+//!
+//! ```xml
+//! <log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="INFO" thread="Render thread">
+//!   <log4j:Message><![CDATA[Setting user: Steve]]></log4j:Message>
+//! </log4j:Event>
+//! ```
+//!
+//! while old versions print plain text like `[12:00:00] [Render thread/INFO]: Setting user: Steve`.
+//! Both formats are supported.
+
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+const EVENT_START: &str = "<log4j:Event";
+const EVENT_END: &str = "</log4j:Event>";
+
+/// Matches an attribute of an XML element, e.g. `level="INFO"`.
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(\w+)="([^"]*)""#).unwrap());
+/// Matches a plain-text log line, e.g. `[12:00:00] [Render thread/INFO]: message`.
+static PLAIN_TEXT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\[([\d:.]+)\] \[(.+?)/(TRACE|DEBUG|INFO|WARN|ERROR|FATAL)\](?: \[(.+?)\])?: (.*)$",
+    )
+    .unwrap()
+});
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LogRecord {
+    /// Milliseconds since the Unix epoch.
+    ///
+    /// Plain-text logs only contain the time of day, so it's `None` for them.
+    pub timestamp: Option<u64>,
+    pub level: LogLevel,
+    pub thread: String,
+    pub logger: String,
+    pub message: String,
+    /// The stack trace attached to this record.
+    pub throwable: Option<String>,
+}
+
+/// A streaming parser that is fed with the standard output of the game line by line.
+#[derive(Default)]
+pub struct LogParser {
+    /// The lines of an unfinished XML event.
+    buffer: String,
+}
+
+impl FromStr for LogLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "TRACE" => Ok(Self::Trace),
+            "DEBUG" => Ok(Self::Debug),
+            "INFO" => Ok(Self::Info),
+            "WARN" => Ok(Self::Warn),
+            "ERROR" => Ok(Self::Error),
+            "FATAL" => Ok(Self::Fatal),
+            _ => Err(()),
+        }
+    }
+}
+
+impl LogParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds a line to the parser.
+    ///
+    /// It returns a record once a whole event has been read,
+    /// or `None` if the event spans more lines.
+    /// Blank lines between events are skipped.
+    pub fn feed(&mut self, line: &str) -> Option<LogRecord> {
+        if self.buffer.is_empty() && line.trim().is_empty() {
+            return None;
+        }
+
+        if self.buffer.is_empty() && !line.trim_start().starts_with(EVENT_START) {
+            return Some(parse_plain_text(line));
+        }
+
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+
+        if line.contains(EVENT_END) {
+            let event = std::mem::take(&mut self.buffer);
+            Some(parse_event(&event).unwrap_or_else(|| parse_plain_text(event.trim_end())))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the unfinished event as a plain-text record, which happens when the game is killed.
+    pub fn finish(&mut self) -> Option<LogRecord> {
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(parse_plain_text(
+                std::mem::take(&mut self.buffer).trim_end(),
+            ))
+        }
+    }
+}
+
+/// Parses a Log4j XML event.
+fn parse_event(event: &str) -> Option<LogRecord> {
+    let start = event.find(EVENT_START)?;
+    let head = &event[start..start + event[start..].find('>')?];
+
+    let mut record = LogRecord::default();
+
+    for caps in ATTRIBUTE.captures_iter(head) {
+        let value = unescape(&caps[2]);
+
+        match &caps[1] {
+            "logger" => record.logger = value,
+            "timestamp" => record.timestamp = value.parse().ok(),
+            "level" => record.level = value.parse().unwrap_or_default(),
+            "thread" => record.thread = value,
+            _ => {}
+        }
+    }
+
+    record.message = element_text(event, "log4j:Message").unwrap_or_default();
+    record.throwable = element_text(event, "log4j:Throwable");
+
+    Some(record)
+}
+
+fn parse_plain_text(line: &str) -> LogRecord {
+    match PLAIN_TEXT.captures(line) {
+        Some(caps) => LogRecord {
+            timestamp: None,
+            level: caps[3].parse().unwrap_or_default(),
+            thread: caps[2].to_string(),
+            logger: caps
+                .get(4)
+                .map(|l| l.as_str().to_string())
+                .unwrap_or_default(),
+            message: caps[5].to_string(),
+            throwable: None,
+        },
+        None => LogRecord {
+            message: line.to_string(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Returns the text inside an element, where CDATA sections are kept as they are,
+/// and other parts are unescaped.
+fn element_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    let mut inner = &xml[start..end];
+
+    let mut text = String::new();
+
+    // Log4j splits a CDATA section if the message itself contains `]]>`.
+    while let Some(cdata_start) = inner.find("<![CDATA[") {
+        text.push_str(&unescape(&inner[..cdata_start]));
+        inner = &inner[cdata_start + "<![CDATA[".len()..];
+
+        let cdata_end = inner.find("]]>").unwrap_or(inner.len());
+        text.push_str(&inner[..cdata_end]);
+        inner = &inner[(cdata_end + "]]>".len()).min(inner.len())..];
+    }
+    text.push_str(&unescape(inner));
+
+    Some(text)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -1,3 +1,214 @@
-const FORGE: &str = "https://files.minecraftforge.net";
-const LITELOADER: &str = "http://dl.liteloader.com/versions/versions.json";
-const BANGBANG93: &str = "https://bmclapi2.bangbang93.com";
+//! # Mods
+//!
+//! Installs mod loaders on top of vanilla versions.
+//!
+//! An installed loader is a version JSON in `versions/<id>/<id>.json`
+//! which inherits from the vanilla version via `inheritsFrom`,
+//! along with the libraries it declares.
+
+pub mod fabric;
+pub mod forge;
+pub mod liteloader;
+pub mod modrinth;
+pub mod neoforge;
+pub mod optifine;
+pub mod quilt;
+
+use crate::constants::{DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::{DownloadError, JsonError};
+use crate::file_system;
+use crate::utils::downloader::{self, CLIENT, Downloader, FileInfo};
+use crate::utils::json_processer;
+use crate::utils::maven::{self, MavenCoordinate};
+
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use serde_json::{Value, json};
+
+/// Libraries without repositories are hosted by Mojang.
+const MOJANG_LIBRARIES: &str = "https://libraries.minecraft.net/";
+/// The main class of LaunchWrapper, which loads the tweakers passed by `--tweakClass`.
+const LAUNCH_WRAPPER: &str = "net.minecraft.launchwrapper.Launch";
+
+/// A version of a mod loader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoaderVersion {
+    /// e.g. `0.16.14`.
+    pub version: String,
+    /// Whether it's a stable release, rather than a beta one.
+    pub stable: bool,
+}
+
+/// Requests JSON from a meta service of mod loaders.
+async fn request_json(url: &str) -> Result<Value, DownloadError> {
+    json_processer::parse_from_string(&request_text(url).await?)
+        .map_err(|e| JsonError::from(e).into())
+}
+
+async fn request_text(url: &str) -> Result<String, DownloadError> {
+    let response = downloader::get_file_from_remote(&CLIENT, url).await?;
+
+    if !response.status().is_success() {
+        return Err(DownloadError::InternetError(format!(
+            "{} responded with {}",
+            url,
+            response.status()
+        )));
+    }
+
+    Ok(response.text().await?)
+}
+
+/// Writes a version JSON into `versions/<id>/<id>.json` under `root`, and returns the ID.
+async fn write_version_json(root: &str, data: &Value) -> Result<String, DownloadError> {
+    let Value::String(id) = &data["id"] else {
+        return Err(DownloadError::JsonError(
+            "The version JSON doesn't have an ID".to_string(),
+        ));
+    };
+
+    let contents = json_processer::convert_to_string(data).map_err(JsonError::from)?;
+
+    file_system::write_into_file(
+        &format!("{}/versions/{}", root, id),
+        &format!("{}.json", id),
+        contents.as_bytes(),
+    )
+    .await?;
+
+    Ok(id.to_owned())
+}
+
+/// Generates a version JSON which launches the parent version with LaunchWrapper and one more tweaker,
+/// whose ID is the parent's one followed by `suffix`.
+///
+/// Versions before 1.13 declare `minecraftArguments`, which replaces the parent's one when merged,
+/// so the parent's arguments are repeated before the tweaker.
+fn tweaker_version_json(
+    parent: &Value,
+    suffix: &str,
+    tweak_class: &str,
+    libraries: Vec<Value>,
+) -> Result<Value, DownloadError> {
+    let Value::String(parent_id) = &parent["id"] else {
+        return Err(DownloadError::JsonError(
+            "The version JSON doesn't have an ID".to_string(),
+        ));
+    };
+
+    let time = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    let mut data = json!({
+        "id": format!("{}-{}", parent_id, suffix),
+        "inheritsFrom": parent_id,
+        "type": parent["type"].as_str().unwrap_or("release"),
+        "time": time,
+        "releaseTime": time,
+        "mainClass": LAUNCH_WRAPPER,
+        "libraries": libraries
+    });
+
+    match &parent["minecraftArguments"] {
+        Value::String(args) => {
+            data["minecraftArguments"] =
+                Value::String(format!("{} --tweakClass {}", args, tweak_class));
+        }
+        _ => data["arguments"] = json!({ "game": ["--tweakClass", tweak_class] }),
+    }
+
+    Ok(data)
+}
+
+/// Downloads the libraries that are only declared by Maven coordinates and repositories, e.g.
+/// `{"name": "net.fabricmc:fabric-loader:0.16.14", "url": "https://maven.fabricmc.net/"}`.
+async fn download_maven_libraries(data: &Value, src: DownloadSource) -> Result<(), DownloadError> {
+    let mut files = Vec::new();
+
+    if let Value::Array(libs) = &data["libraries"] {
+        for lib in libs {
+            let (Value::String(name), Value::String(repository)) = (&lib["name"], &lib["url"])
+            else {
+                continue;
+            };
+            let Some(coordinate) = MavenCoordinate::parse(name) else {
+                return Err(DownloadError::JsonError(format!(
+                    "Invalid Maven coordinate `{}`",
+                    name
+                )));
+            };
+
+            // Only the repositories mirrored by BMCLAPI are redirected, e.g. Quilt's one is not.
+            let url = maven::mirror_url(&coordinate.url(repository), src);
+
+            // Loaders rarely declare hashes, so the ones published along with artifacts are used.
+            let sha1 = match &lib["sha1"] {
+                Value::String(sha1) => Some(sha1.to_owned()),
+                _ => maven::request_sha1(&url).await?,
+            };
+
+            files.push(FileInfo {
+                path: Cow::from(PathBuf::from(format!(
+                    "{}/libraries/{}",
+                    MINECRAFT_ROOT,
+                    coordinate.directory()
+                ))),
+                url: url.into(),
+                name: Cow::from(coordinate.file_name()),
+                sha1: sha1.map(Cow::from),
+            });
+        }
+    }
+
+    let mut num = files.len();
+
+    for file_info in files {
+        println!("Remains {num} library files");
+
+        let downloader = Downloader::new(&CLIENT, file_info);
+        downloader.download_file().await?;
+
+        num -= 1;
+    }
+
+    Ok(())
+}
+
+/// Downloads an installer jar into `libraries`, and returns its path.
+async fn download_installer(repository: &str, coordinate: &str) -> Result<PathBuf, DownloadError> {
+    let Some(coordinate) = MavenCoordinate::parse(coordinate) else {
+        return Err(DownloadError::OtherError(format!(
+            "Invalid Maven coordinate `{}`",
+            coordinate
+        )));
+    };
+
+    let path = PathBuf::from(format!(
+        "{}/libraries/{}",
+        MINECRAFT_ROOT,
+        coordinate.directory()
+    ));
+    let file_name = coordinate.file_name();
+    let file_info = FileInfo {
+        path: Cow::from(path.as_path()),
+        name: Cow::from(file_name.as_str()),
+        url: coordinate.url(repository).into(),
+        sha1: None,
+    };
+    let downloader = Downloader::new(&CLIENT, file_info);
+    downloader.download_file().await?;
+
+    Ok(path.join(file_name))
+}
+
+/// Returns where a library is stored under `root`,
+/// e.g. `.minecraft/libraries/org/ow2/asm/asm/9.8/asm-9.8.jar`.
+fn library_path(root: &str, name: &str) -> Option<PathBuf> {
+    let coordinate = MavenCoordinate::parse(name)?;
+
+    Some(PathBuf::from(format!(
+        "{}/libraries/{}",
+        root,
+        coordinate.path()
+    )))
+}
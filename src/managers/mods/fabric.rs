@@ -0,0 +1,114 @@
+//! # Fabric
+//!
+//! Installs Fabric loader with its meta service, which generates the whole version JSON,
+//! including the loader and the intermediary mappings of the game version.
+//!
+//! The vanilla version must be downloaded by [`MinecraftDownloader`] as well,
+//! since the Fabric version inherits from it.
+//!
+//! [`MinecraftDownloader`]: crate::managers::game::download::MinecraftDownloader
+
+use crate::constants::{BANGBANG93, Category, DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::DownloadError;
+use crate::managers::mods::{self, LoaderVersion};
+
+use serde_json::Value;
+
+const FABRIC_META: &str = "https://meta.fabricmc.net";
+
+pub struct FabricInstaller {
+    game_version: String,
+    loader_version: String,
+    source: DownloadSource,
+    category: Category,
+}
+
+impl FabricInstaller {
+    pub fn new(
+        game_version: &str,
+        loader_version: &str,
+        src: DownloadSource,
+        category: Category,
+    ) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            loader_version: loader_version.to_string(),
+            source: src,
+            category,
+        }
+    }
+
+    /// Writes the version JSON and downloads the libraries of Fabric,
+    /// and returns the ID of the installed version, e.g. `fabric-loader-0.16.14-1.21.5`.
+    ///
+    /// The server version is suffixed with `-server`, so that it doesn't replace the client one.
+    pub async fn install(&self) -> Result<String, DownloadError> {
+        let mut data = mods::request_json(&format!(
+            "{}/v2/versions/loader/{}/{}/{}/json",
+            meta(self.source),
+            self.game_version,
+            self.loader_version,
+            match self.category {
+                Category::Client => "profile",
+                Category::Server => "server",
+            }
+        ))
+        .await?;
+
+        if let (Category::Server, Value::String(id)) = (self.category, &data["id"]) {
+            data["id"] = Value::String(format!("{}-server", id));
+        }
+
+        mods::download_maven_libraries(&data, self.source).await?;
+        mods::write_version_json(MINECRAFT_ROOT, &data).await
+    }
+}
+
+/// Lists the Fabric loader versions that support a game version, from the newest to the oldest.
+pub async fn list_loader_versions(
+    game_version: &str,
+    src: DownloadSource,
+) -> Result<Vec<LoaderVersion>, DownloadError> {
+    let data = mods::request_json(&format!(
+        "{}/v2/versions/loader/{}",
+        meta(src),
+        game_version
+    ))
+    .await?;
+
+    Ok(loader_versions_from(&data))
+}
+
+/// Parses the loader versions of a game version, e.g.
+///
+/// ```json
+/// [
+///     {
+///         "loader": { "maven": "net.fabricmc:fabric-loader:0.16.14", "version": "0.16.14", "stable": true },
+///         "intermediary": { "maven": "net.fabricmc:intermediary:1.21.5", "version": "1.21.5", "stable": true },
+///         "launcherMeta": { ... }
+///     }
+/// ]
+/// ```
+pub fn loader_versions_from(data: &Value) -> Vec<LoaderVersion> {
+    data.as_array()
+        .map(|versions| {
+            versions
+                .iter()
+                .filter_map(|v| {
+                    Some(LoaderVersion {
+                        version: v["loader"]["version"].as_str()?.to_string(),
+                        stable: v["loader"]["stable"].as_bool().unwrap_or_default(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn meta(src: DownloadSource) -> String {
+    match src {
+        DownloadSource::Official => FABRIC_META.to_string(),
+        DownloadSource::Bangbang93 => format!("{}/fabric-meta", BANGBANG93),
+    }
+}
@@ -0,0 +1,579 @@
+//! # Forge
+//!
+//! Installs Forge with its official installer jar, without running the installer itself.
+//!
+//! Since 1.13, the installer ships `install_profile.json` which declares processors,
+//! i.e. Java programs that deobfuscate and patch the game jar.
+//! They're run here with `java` in `PATH`, and their outputs are checked against the declared SHA1.
+//! Older installers only ship the version JSON and the universal jar of Forge.
+//!
+//! The vanilla version must be downloaded by [`MinecraftDownloader`] before installing,
+//! since processors patch its jar.
+//!
+//! [`MinecraftDownloader`]: crate::managers::game::download::MinecraftDownloader
+
+use crate::constants::{BANGBANG93, Category, DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::{DownloadError, InstallError};
+use crate::file_system;
+use crate::managers::mods::{self, LoaderVersion};
+use crate::utils::downloader::{CLIENT, Downloader, FileInfo};
+use crate::utils::maven::{self, MavenCoordinate};
+use crate::utils::{json_processer, sha1_checker};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+use tokio::process::Command;
+use zip::ZipArchive;
+
+const FORGE: &str = "https://files.minecraftforge.net";
+const FORGE_MAVEN: &str = "https://maven.minecraftforge.net";
+
+/// `{KEY}` in an argument of a processor.
+static DATA_REFERENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)}").unwrap());
+
+pub(super) type Installer = ZipArchive<Cursor<Vec<u8>>>;
+
+pub struct ForgeInstaller {
+    game_version: String,
+    forge_version: String,
+    source: DownloadSource,
+    category: Category,
+}
+
+impl ForgeInstaller {
+    /// `forge_version` is the version without the game version, e.g. `47.4.0` for 1.20.1.
+    pub fn new(
+        game_version: &str,
+        forge_version: &str,
+        src: DownloadSource,
+        category: Category,
+    ) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            forge_version: forge_version.to_string(),
+            source: src,
+            category,
+        }
+    }
+
+    /// Downloads the installer, installs Forge with it,
+    /// and returns the ID of the installed version, e.g. `1.20.1-forge-47.4.0`.
+    ///
+    /// The server version is suffixed with `-server`, so that it doesn't replace the client one.
+    pub async fn install(&self) -> Result<String, InstallError> {
+        let repository = match self.source {
+            DownloadSource::Official => FORGE_MAVEN.to_string(),
+            DownloadSource::Bangbang93 => format!("{}/maven", BANGBANG93),
+        };
+        let installer = mods::download_installer(
+            &repository,
+            &format!(
+                "net.minecraftforge:forge:{}-{}:installer",
+                self.game_version, self.forge_version
+            ),
+        )
+        .await?;
+
+        install_with_installer(&installer, self.source, self.category).await
+    }
+}
+
+/// Lists the recommended and the latest Forge versions of a game version.
+pub async fn promoted_versions(game_version: &str) -> Result<Vec<LoaderVersion>, DownloadError> {
+    let data = mods::request_json(&format!(
+        "{}/net/minecraftforge/forge/promotions_slim.json",
+        FORGE
+    ))
+    .await?;
+
+    Ok(promoted_versions_from(&data, game_version))
+}
+
+/// Parses the promoted versions of a game version, e.g.
+/// `{"promos": {"1.20.1-recommended": "47.4.0", "1.20.1-latest": "47.4.1"}}`.
+///
+/// The recommended version is stable, and comes before the latest one.
+pub fn promoted_versions_from(data: &Value, game_version: &str) -> Vec<LoaderVersion> {
+    let mut versions = Vec::new();
+
+    for (promotion, stable) in [("recommended", true), ("latest", false)] {
+        if let Value::String(version) = &data["promos"][format!("{}-{}", game_version, promotion)]
+            && versions
+                .iter()
+                .all(|v: &LoaderVersion| &v.version != version)
+        {
+            versions.push(LoaderVersion {
+                version: version.to_owned(),
+                stable,
+            });
+        }
+    }
+
+    versions
+}
+
+/// Installs with a local installer jar in the format of Forge, which NeoForge shares,
+/// and returns the ID of the installed version.
+pub async fn install_with_installer(
+    installer: &Path,
+    src: DownloadSource,
+    category: Category,
+) -> Result<String, InstallError> {
+    install_into(installer, MINECRAFT_ROOT, src, category).await
+}
+
+/// Installs with a local installer jar into the game directory `root` rather than `.minecraft`,
+/// where the vanilla version must be downloaded, and returns the ID of the installed version.
+pub async fn install_into(
+    installer: &Path,
+    root: &str,
+    src: DownloadSource,
+    category: Category,
+) -> Result<String, InstallError> {
+    let mut archive = ZipArchive::new(Cursor::new(tokio::fs::read(installer).await?))?;
+    let profile = read_json_entry(&mut archive, "install_profile.json")?;
+
+    let mut data = if profile["install"].is_object() {
+        install_legacy(&mut archive, &profile, root, src, category).await?
+    } else {
+        let entry = profile["json"].as_str().unwrap_or("/version.json");
+        let data = read_json_entry(&mut archive, entry.trim_start_matches('/'))?;
+
+        // Libraries of processors are only needed during installation.
+        download_artifacts(&mut archive, &profile["libraries"], root, src).await?;
+        download_artifacts(&mut archive, &data["libraries"], root, src).await?;
+        run_processors(&mut archive, &profile, root, installer, category).await?;
+
+        data
+    };
+
+    if let (Category::Server, Value::String(id)) = (category, &data["id"]) {
+        data["id"] = Value::String(format!("{}-server", id));
+    }
+
+    Ok(mods::write_version_json(root, &data).await?)
+}
+
+/// Installs with an installer before 1.13, whose profile looks like
+///
+/// ```json
+/// {
+///     "install": {
+///         "path": "net.minecraftforge:forge:1.12.2-14.23.5.2860",
+///         "filePath": "forge-1.12.2-14.23.5.2860.jar",
+///         "minecraft": "1.12.2"
+///     },
+///     "versionInfo": { "id": "1.12.2-forge-14.23.5.2860", "libraries": [...], ... }
+/// }
+/// ```
+///
+/// and returns the version JSON.
+async fn install_legacy(
+    archive: &mut Installer,
+    profile: &Value,
+    root: &str,
+    src: DownloadSource,
+    category: Category,
+) -> Result<Value, InstallError> {
+    let install = &profile["install"];
+    let (Value::String(path), Value::String(file_path), Value::String(minecraft)) = (
+        &install["path"],
+        &install["filePath"],
+        &install["minecraft"],
+    ) else {
+        return Err(InstallError::InvalidInstaller(
+            "`install_profile.json` is incomplete".to_string(),
+        ));
+    };
+
+    // The universal jar of Forge is bundled in the installer.
+    if !extract_entry(archive, file_path, &library(root, path)?).await? {
+        return Err(InstallError::InvalidInstaller(format!(
+            "`{}` is not in the installer",
+            file_path
+        )));
+    }
+
+    let mut data = profile["versionInfo"].clone();
+
+    // Very old installers don't declare the parent version.
+    if data["inheritsFrom"].is_null() {
+        data["inheritsFrom"] = Value::String(minecraft.to_owned());
+    }
+
+    let required = match category {
+        Category::Client => "clientreq",
+        Category::Server => "serverreq",
+    };
+
+    if let Value::Array(libs) = &data["libraries"] {
+        let mut num = libs.len();
+
+        for lib in libs {
+            num -= 1;
+
+            let Value::String(name) = &lib["name"] else {
+                continue;
+            };
+            if name == path || lib[required] == Value::Bool(false) {
+                continue;
+            }
+            let Some(coordinate) = MavenCoordinate::parse(name) else {
+                continue;
+            };
+
+            let repository = lib["url"].as_str().unwrap_or(mods::MOJANG_LIBRARIES);
+            let url = maven::mirror_url(&coordinate.url(repository), src);
+
+            // Forge's libraries declare their hashes in `checksums`,
+            // while the ones hosted by Mojang only publish them along with the artifacts.
+            let sha1 = match &lib["checksums"][0] {
+                Value::String(sha1) => Some(sha1.to_owned()),
+                _ => maven::request_sha1(&url).await?,
+            };
+
+            println!("Remains {num} library files");
+
+            let file_info = FileInfo {
+                path: Cow::from(PathBuf::from(format!(
+                    "{}/libraries/{}",
+                    root,
+                    coordinate.directory()
+                ))),
+                name: Cow::from(coordinate.file_name()),
+                url: url.into(),
+                sha1: sha1.map(Cow::from),
+            };
+            let downloader = Downloader::new(&CLIENT, file_info);
+            downloader.download_file().await?;
+        }
+    }
+
+    Ok(data)
+}
+
+/// Downloads the libraries declared with `downloads.artifact`.
+///
+/// Artifacts without URLs are bundled in the installer under `maven/`,
+/// or they are produced by processors later.
+async fn download_artifacts(
+    archive: &mut Installer,
+    libs: &Value,
+    root: &str,
+    src: DownloadSource,
+) -> Result<(), InstallError> {
+    let Value::Array(libs) = libs else {
+        return Ok(());
+    };
+
+    let mut num = libs.len();
+
+    for lib in libs {
+        num -= 1;
+
+        let artifact = &lib["downloads"]["artifact"];
+        let Value::String(path) = &artifact["path"] else {
+            continue;
+        };
+        let target = PathBuf::from(format!("{}/libraries/{}", root, path));
+
+        match artifact["url"].as_str() {
+            Some(url) if !url.is_empty() => {
+                println!("Remains {num} library files");
+
+                let (file_path, file_name) = path.rsplit_once('/').unwrap_or(("", path));
+                let file_info = FileInfo {
+                    path: Cow::from(PathBuf::from(format!("{}/libraries/{}", root, file_path))),
+                    name: Cow::from(file_name),
+                    url: maven::mirror_url(url, src).into(),
+                    sha1: artifact["sha1"].as_str().map(Cow::from),
+                };
+                let downloader = Downloader::new(&CLIENT, file_info);
+                downloader.download_file().await?;
+            }
+            _ => {
+                extract_entry(archive, &format!("maven/{}", path), &target).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the processors of the side, which are skipped if their outputs are already in place.
+async fn run_processors(
+    archive: &mut Installer,
+    profile: &Value,
+    root: &str,
+    installer: &Path,
+    category: Category,
+) -> Result<(), InstallError> {
+    let Value::String(minecraft) = &profile["minecraft"] else {
+        return Err(InstallError::InvalidInstaller(
+            "`install_profile.json` doesn't declare the game version".to_string(),
+        ));
+    };
+
+    let side = category.to_string();
+    let game_jar = format!("{}/versions/{}/{}.jar", root, minecraft, minecraft);
+
+    if !Path::new(&game_jar).exists() {
+        return Err(InstallError::ProcessorError(format!(
+            "Minecraft {} must be downloaded before installing",
+            minecraft
+        )));
+    }
+
+    // Files in the installer which are referred by data, e.g. `/data/client.lzma`, are extracted here.
+    let temp = env::temp_dir().join(format!("gridcore-installer-{}", std::process::id()));
+
+    let mut data = HashMap::from([
+        ("SIDE".to_string(), side.clone()),
+        ("MINECRAFT_JAR".to_string(), game_jar),
+        ("MINECRAFT_VERSION".to_string(), minecraft.to_owned()),
+        ("ROOT".to_string(), root.to_string()),
+        (
+            "INSTALLER".to_string(),
+            installer.to_string_lossy().into_owned(),
+        ),
+        ("LIBRARY_DIR".to_string(), format!("{}/libraries", root)),
+    ]);
+
+    if let Value::Object(entries) = &profile["data"] {
+        for (key, value) in entries {
+            let Value::String(value) = &value[&side] else {
+                continue;
+            };
+
+            let value = match value.strip_prefix('/') {
+                Some(entry) => {
+                    let target = temp.join(entry);
+
+                    if !extract_entry(archive, entry, &target).await? {
+                        return Err(InstallError::InvalidInstaller(format!(
+                            "`{}` is not in the installer",
+                            entry
+                        )));
+                    }
+
+                    target.to_string_lossy().into_owned()
+                }
+                None => substitute(value, root, &HashMap::new())?,
+            };
+
+            data.insert(key.to_owned(), value);
+        }
+    }
+
+    let result = run_processors_inner(&profile["processors"], root, &side, &data).await;
+    let _ = tokio::fs::remove_dir_all(&temp).await;
+
+    result
+}
+
+async fn run_processors_inner(
+    processors: &Value,
+    root: &str,
+    side: &str,
+    data: &HashMap<String, String>,
+) -> Result<(), InstallError> {
+    let Value::Array(processors) = processors else {
+        return Ok(());
+    };
+
+    for processor in processors {
+        if let Value::Array(sides) = &processor["sides"]
+            && !sides.iter().any(|s| s == side)
+        {
+            continue;
+        }
+
+        let Value::String(jar) = &processor["jar"] else {
+            return Err(InstallError::InvalidInstaller(
+                "A processor doesn't declare its jar".to_string(),
+            ));
+        };
+        let jar = library(root, jar)?;
+
+        let mut classpath = vec![jar.to_string_lossy().into_owned()];
+        if let Value::Array(libs) = &processor["classpath"] {
+            for lib in libs.iter().filter_map(|lib| lib.as_str()) {
+                classpath.push(library(root, lib)?.to_string_lossy().into_owned());
+            }
+        }
+
+        let mut args = Vec::new();
+        if let Value::Array(values) = &processor["args"] {
+            for arg in values.iter().filter_map(|arg| arg.as_str()) {
+                args.push(substitute(arg, root, data)?);
+            }
+        }
+
+        let mut outputs = Vec::new();
+        if let Value::Object(values) = &processor["outputs"] {
+            for (file, sha1) in values {
+                outputs.push((
+                    PathBuf::from(substitute(file, root, data)?),
+                    substitute(sha1.as_str().unwrap_or_default(), root, data)?,
+                ));
+            }
+        }
+
+        if !outputs.is_empty() && outputs_match(&outputs).await? {
+            continue;
+        }
+
+        let status = Command::new("java")
+            .arg("-cp")
+            .arg(classpath.join(if cfg!(windows) { ";" } else { ":" }))
+            .arg(main_class(&jar).await?)
+            .args(&args)
+            .status()
+            .await?;
+
+        if !status.success() {
+            return Err(InstallError::ProcessorError(format!(
+                "{} exited with {}",
+                jar.display(),
+                status
+            )));
+        }
+
+        if !outputs_match(&outputs).await? {
+            return Err(InstallError::ProcessorError(format!(
+                "The outputs of {} don't match their checksums",
+                jar.display()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the references in an argument of a processor:
+///
+/// - `[group:artifact:version]` is the path of a library under `root`.
+/// - `'text'` is a literal.
+/// - `{KEY}` is an entry of data, which may appear anywhere in the argument.
+pub fn substitute(
+    arg: &str,
+    root: &str,
+    data: &HashMap<String, String>,
+) -> Result<String, InstallError> {
+    if let Some(name) = arg.strip_prefix('[').and_then(|arg| arg.strip_suffix(']')) {
+        return Ok(library(root, name)?.to_string_lossy().into_owned());
+    }
+
+    if let Some(literal) = arg
+        .strip_prefix('\'')
+        .and_then(|arg| arg.strip_suffix('\''))
+    {
+        return Ok(literal.to_owned());
+    }
+
+    let mut missing = None;
+
+    let arg = DATA_REFERENCE.replace_all(arg, |caps: &Captures| match data.get(&caps[1]) {
+        Some(value) => value.to_owned(),
+        None => {
+            missing = Some(caps[1].to_owned());
+            caps[0].to_owned()
+        }
+    });
+
+    match missing {
+        Some(key) => Err(InstallError::InvalidInstaller(format!(
+            "Data `{}` is not declared",
+            key
+        ))),
+        None => Ok(arg.into_owned()),
+    }
+}
+
+fn library(root: &str, name: &str) -> Result<PathBuf, InstallError> {
+    mods::library_path(root, name).ok_or_else(|| {
+        InstallError::InvalidInstaller(format!("Invalid Maven coordinate `{}`", name))
+    })
+}
+
+/// Checks whether every output exists and matches its SHA1.
+async fn outputs_match(outputs: &[(PathBuf, String)]) -> Result<bool, InstallError> {
+    for (file, sha1) in outputs {
+        let (Some(file_path), Some(file_name)) = (file.parent(), file.file_name()) else {
+            return Ok(false);
+        };
+
+        if !file.exists()
+            || &sha1_checker::calculate_sha1(&file_path, &file_name.to_string_lossy()).await?
+                != sha1
+        {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Reads `Main-Class` in the manifest of a jar.
+async fn main_class(jar: &Path) -> Result<String, InstallError> {
+    let mut archive = ZipArchive::new(Cursor::new(tokio::fs::read(jar).await?))?;
+    let mut manifest = String::new();
+    archive
+        .by_name("META-INF/MANIFEST.MF")?
+        .read_to_string(&mut manifest)?;
+
+    // Long lines are wrapped, and continued with a leading space.
+    manifest
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .lines()
+        .find_map(|line| line.strip_prefix("Main-Class:"))
+        .map(|main_class| main_class.trim().to_owned())
+        .ok_or_else(|| {
+            InstallError::ProcessorError(format!(
+                "{} doesn't declare its main class",
+                jar.display()
+            ))
+        })
+}
+
+fn read_json_entry(archive: &mut Installer, name: &str) -> Result<Value, InstallError> {
+    let mut contents = String::new();
+    archive.by_name(name)?.read_to_string(&mut contents)?;
+
+    json_processer::parse_from_string(&contents)
+        .map_err(|e| InstallError::InvalidInstaller(format!("`{}`: {}", name, e)))
+}
+
+/// Extracts an entry of the installer, and returns `false` if it doesn't exist.
+pub(super) async fn extract_entry(
+    archive: &mut Installer,
+    name: &str,
+    target: &Path,
+) -> Result<bool, InstallError> {
+    let mut contents = Vec::new();
+
+    match archive.by_name(name) {
+        Ok(mut entry) => entry.read_to_end(&mut contents)?,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    let (Some(file_path), Some(file_name)) = (target.parent(), target.file_name()) else {
+        return Err(InstallError::InvalidInstaller(format!(
+            "Invalid path `{}`",
+            target.display()
+        )));
+    };
+
+    file_system::write_into_file(&file_path, &file_name.to_string_lossy(), &contents).await?;
+
+    Ok(true)
+}
@@ -0,0 +1,188 @@
+//! # LiteLoader
+//!
+//! Installs LiteLoader, which is loaded by LaunchWrapper as a tweaker,
+//! on top of a vanilla version or a legacy Forge version.
+//!
+//! LiteLoader is only published for the versions before 1.13,
+//! and the versions since 1.12 are only published as snapshots.
+//!
+//! The version which LiteLoader is installed on must be installed first,
+//! since the LiteLoader version inherits from it.
+
+use crate::constants::{BANGBANG93, DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::DownloadError;
+use crate::managers::mods::{self, LoaderVersion};
+use crate::managers::version;
+
+use std::cmp::Reverse;
+
+use serde_json::{Value, json};
+
+const LITELOADER: &str = "http://dl.liteloader.com/versions/versions.json";
+const LITELOADER_REPOSITORY: &str = "http://dl.liteloader.com/versions/";
+const ARTEFACT: &str = "com.mumfrey:liteloader";
+const TWEAKER: &str = "com.mumfrey.liteloader.launch.LiteLoaderTweaker";
+
+pub struct LiteLoaderInstaller {
+    game_version: String,
+    liteloader_version: String,
+    parent: String,
+    source: DownloadSource,
+}
+
+impl LiteLoaderInstaller {
+    /// `parent` is the installed version which LiteLoader is installed on,
+    /// e.g. `1.12.2` or `1.12.2-forge-14.23.5.2860`.
+    pub fn new(
+        game_version: &str,
+        liteloader_version: &str,
+        parent: &str,
+        src: DownloadSource,
+    ) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            liteloader_version: liteloader_version.to_string(),
+            parent: parent.to_string(),
+            source: src,
+        }
+    }
+
+    /// Writes the version JSON and downloads the libraries of LiteLoader,
+    /// and returns the ID of the installed version, e.g. `1.12.2-liteloader-1.12.2-SNAPSHOT`.
+    pub async fn install(&self) -> Result<String, DownloadError> {
+        let data = mods::request_json(&versions_url(self.source)).await?;
+        let parent = version::read_version_json(&self.parent).await?;
+
+        let data = version_json_from(&data, &parent, &self.game_version, &self.liteloader_version)?;
+
+        mods::download_maven_libraries(&data, self.source).await?;
+        mods::write_version_json(MINECRAFT_ROOT, &data).await
+    }
+}
+
+/// Lists the LiteLoader versions of a game version, from the newest to the oldest.
+pub async fn list_versions(
+    game_version: &str,
+    src: DownloadSource,
+) -> Result<Vec<LoaderVersion>, DownloadError> {
+    let data = mods::request_json(&versions_url(src)).await?;
+
+    Ok(versions_from(&data, game_version))
+}
+
+/// Picks the versions of a game version from `versions.json`, e.g.
+///
+/// ```json
+/// {
+///     "versions": {
+///         "1.12.2": {
+///             "repo": { "url": "http://repo.mumfrey.com/content/repositories/snapshots/" },
+///             "snapshots": {
+///                 "libraries": [{ "name": "net.minecraft:launchwrapper:1.12" }],
+///                 "com.mumfrey:liteloader": {
+///                     "latest": { "version": "1.12.2-SNAPSHOT", ... },
+///                     "1.12.2-SNAPSHOT": { "version": "1.12.2-SNAPSHOT", "timestamp": "1531059153", ... }
+///                 }
+///             }
+///         }
+///     }
+/// }
+/// ```
+///
+/// Releases are listed in `artefacts` instead, and only they are stable.
+pub fn versions_from(data: &Value, game_version: &str) -> Vec<LoaderVersion> {
+    let mut versions = Vec::new();
+
+    for (group, stable) in [("artefacts", true), ("snapshots", false)] {
+        let Value::Object(artefacts) = &data["versions"][game_version][group][ARTEFACT] else {
+            continue;
+        };
+
+        for (key, artefact) in artefacts {
+            // `latest` duplicates one of the versions.
+            if key == "latest" {
+                continue;
+            }
+            let Value::String(version) = &artefact["version"] else {
+                continue;
+            };
+
+            let timestamp = artefact["timestamp"]
+                .as_str()
+                .and_then(|timestamp| timestamp.parse::<u64>().ok())
+                .unwrap_or_default();
+
+            versions.push((
+                timestamp,
+                LoaderVersion {
+                    version: version.to_owned(),
+                    stable,
+                },
+            ));
+        }
+    }
+
+    versions.sort_by_key(|(timestamp, _)| Reverse(*timestamp));
+
+    versions.into_iter().map(|(_, version)| version).collect()
+}
+
+/// Generates the version JSON of a LiteLoader version, which inherits from `parent`,
+/// i.e. the merged JSON of the version which LiteLoader is installed on.
+pub fn version_json_from(
+    data: &Value,
+    parent: &Value,
+    game_version: &str,
+    liteloader_version: &str,
+) -> Result<Value, DownloadError> {
+    let entry = &data["versions"][game_version];
+    let Some((group, artefact)) = ["artefacts", "snapshots"].into_iter().find_map(|group| {
+        let artefact = &entry[group][ARTEFACT][liteloader_version];
+        artefact.is_object().then_some((&entry[group], artefact))
+    }) else {
+        return Err(DownloadError::JsonError(format!(
+            "LiteLoader {} for {} is not found",
+            liteloader_version, game_version
+        )));
+    };
+
+    let repository = entry["repo"]["url"]
+        .as_str()
+        .unwrap_or(LITELOADER_REPOSITORY);
+    let tweak_class = artefact["tweakClass"].as_str().unwrap_or(TWEAKER);
+
+    let mut libraries = vec![json!({
+        "name": format!("{}:{}", ARTEFACT, liteloader_version),
+        "url": repository
+    })];
+
+    // Snapshots declare their libraries in the group.
+    let libs = match &artefact["libraries"] {
+        Value::Array(libs) => Some(libs),
+        _ => group["libraries"].as_array(),
+    };
+
+    for lib in libs.into_iter().flatten() {
+        let mut lib = lib.clone();
+        if lib["url"].is_null() {
+            lib["url"] = Value::String(mods::MOJANG_LIBRARIES.to_string());
+        }
+        libraries.push(lib);
+    }
+
+    mods::tweaker_version_json(
+        parent,
+        &format!("liteloader-{}", liteloader_version),
+        tweak_class,
+        libraries,
+    )
+}
+
+fn versions_url(src: DownloadSource) -> String {
+    match src {
+        DownloadSource::Official => LITELOADER.to_string(),
+        DownloadSource::Bangbang93 => {
+            format!("{}/maven/com/mumfrey/liteloader/versions.json", BANGBANG93)
+        }
+    }
+}
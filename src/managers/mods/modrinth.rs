@@ -0,0 +1,487 @@
+//! # Modrinth
+//!
+//! Searches and installs mods from [Modrinth](https://modrinth.com) via its v2 API.
+//!
+//! Before anything is written into the `mods` folder of an instance,
+//! required dependencies are resolved recursively, and the whole set of versions is checked
+//! against the loader of the instance and the incompatibilities declared by each version,
+//! including the ones of the mods already installed.
+
+use crate::error_handling::ModrinthError;
+use crate::file_system;
+use crate::utils::downloader::CLIENT;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use reqwest::header::USER_AGENT;
+use reqwest::{RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use sha2::{Digest, Sha512};
+
+const MODRINTH_API: &str = "https://api.modrinth.com/v2";
+/// Modrinth asks clients to identify themselves.
+const USER_AGENT_VALUE: &str = concat!("gridcore/", env!("CARGO_PKG_VERSION"));
+
+/// A client that talks to Modrinth API, or a compatible stand-in.
+pub struct ModrinthClient {
+    /// The API root, e.g. `https://api.modrinth.com/v2`.
+    api_root: String,
+}
+
+/// The filters of a search. Empty filters are omitted.
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    pub query: String,
+    /// e.g. `1.21.5`.
+    pub game_version: Option<String>,
+    /// e.g. `fabric`.
+    pub loader: Option<String>,
+    /// e.g. `optimization`. Projects must be in all of them.
+    pub categories: Vec<String>,
+    pub offset: u32,
+    /// Modrinth returns 10 hits if it's 0.
+    pub limit: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub offset: u32,
+    pub limit: u32,
+    pub total_hits: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SearchHit {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    pub description: String,
+    /// Including the loaders.
+    pub categories: Vec<String>,
+    /// `mod`, `modpack`, `resourcepack` or `shader`.
+    pub project_type: String,
+    pub downloads: u64,
+    /// The game versions supported by any version of the project.
+    pub versions: Vec<String>,
+}
+
+/// A version of a project.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    /// `release`, `beta` or `alpha`.
+    pub version_type: String,
+    pub game_versions: Vec<String>,
+    pub loaders: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    pub files: Vec<VersionFile>,
+}
+
+/// A dependency refers to a project, or a specific version of it.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Dependency {
+    pub version_id: Option<String>,
+    pub project_id: Option<String>,
+    pub dependency_type: DependencyType,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyType {
+    Required,
+    Optional,
+    Incompatible,
+    /// The dependency is bundled in the file, so it needn't be installed.
+    Embedded,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct VersionFile {
+    pub hashes: FileHashes,
+    pub url: String,
+    pub filename: String,
+    pub primary: bool,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct FileHashes {
+    pub sha1: String,
+    pub sha512: String,
+}
+
+/// A jar in the `mods` folder, identified by Modrinth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InstalledMod {
+    pub path: PathBuf,
+    pub version: ModrinthVersion,
+}
+
+impl ModrinthClient {
+    pub fn new() -> Self {
+        Self::with_api_root(MODRINTH_API)
+    }
+
+    /// Talks to another API root, e.g. a stand-in server in tests.
+    pub fn with_api_root(api_root: &str) -> Self {
+        Self {
+            api_root: api_root.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn api_root(&self) -> &str {
+        &self.api_root
+    }
+
+    pub async fn search(&self, query: &SearchQuery) -> Result<SearchResults, ModrinthError> {
+        // Facets in the same array are OR-ed, and the arrays are AND-ed.
+        let mut facets = vec![vec!["project_type:mod".to_string()]];
+
+        if let Some(game_version) = &query.game_version {
+            facets.push(vec![format!("versions:{}", game_version)]);
+        }
+        if let Some(loader) = &query.loader {
+            facets.push(vec![format!("categories:{}", loader)]);
+        }
+        for category in &query.categories {
+            facets.push(vec![format!("categories:{}", category)]);
+        }
+
+        let mut params = vec![
+            ("query", query.query.clone()),
+            ("facets", json!(facets).to_string()),
+            ("offset", query.offset.to_string()),
+        ];
+        if query.limit > 0 {
+            params.push(("limit", query.limit.to_string()));
+        }
+
+        self.send(
+            CLIENT
+                .get(format!("{}/search", self.api_root))
+                .query(&params),
+            "search",
+        )
+        .await
+    }
+
+    /// Lists the versions of a project for a game version and a loader, from the newest to the oldest.
+    ///
+    /// `project` is either the ID or the slug of the project.
+    pub async fn list_versions(
+        &self,
+        project: &str,
+        game_version: &str,
+        loader: &str,
+    ) -> Result<Vec<ModrinthVersion>, ModrinthError> {
+        self.send(
+            CLIENT
+                .get(format!("{}/project/{}/version", self.api_root, project))
+                .query(&[
+                    ("game_versions", json!([game_version]).to_string()),
+                    ("loaders", json!([loader]).to_string()),
+                ]),
+            &format!("Project `{}`", project),
+        )
+        .await
+    }
+
+    pub async fn get_version(&self, id: &str) -> Result<ModrinthVersion, ModrinthError> {
+        self.send(
+            CLIENT.get(format!("{}/version/{}", self.api_root, id)),
+            &format!("Version `{}`", id),
+        )
+        .await
+    }
+
+    /// Resolves the required dependencies of a version recursively,
+    /// and returns the versions to be installed, starting with the given one.
+    ///
+    /// Projects in `installed` are neither installed again, nor their dependencies resolved,
+    /// except the project of the given version, whose installed version is replaced.
+    /// Every version is checked against the loader, and the incompatibilities declared
+    /// by the versions to be installed and the installed ones which are not replaced.
+    pub async fn resolve(
+        &self,
+        version: ModrinthVersion,
+        game_version: &str,
+        loader: &str,
+        installed: &[ModrinthVersion],
+    ) -> Result<Vec<ModrinthVersion>, ModrinthError> {
+        // The given version is installed even if another version of its project is.
+        let mut projects = installed
+            .iter()
+            .map(|v| v.project_id.clone())
+            .filter(|project| project != &version.project_id)
+            .collect::<HashSet<_>>();
+        let mut queue = VecDeque::from([version]);
+        let mut plan = Vec::new();
+
+        while let Some(version) = queue.pop_front() {
+            if !projects.insert(version.project_id.clone()) {
+                continue;
+            }
+
+            if !version.loaders.iter().any(|l| l == loader) {
+                return Err(ModrinthError::MissingLoader(format!(
+                    "{} {} requires {}, but the instance uses {}",
+                    version.name,
+                    version.version_number,
+                    version.loaders.join(" or "),
+                    loader
+                )));
+            }
+
+            for dependency in &version.dependencies {
+                if dependency.dependency_type != DependencyType::Required
+                    || dependency
+                        .project_id
+                        .as_ref()
+                        .is_some_and(|project| projects.contains(project))
+                {
+                    continue;
+                }
+
+                let dependency = match (&dependency.version_id, &dependency.project_id) {
+                    (Some(version_id), _) => self.get_version(version_id).await?,
+                    (None, Some(project)) => self
+                        .list_versions(project, game_version, loader)
+                        .await?
+                        .into_iter()
+                        .next()
+                        .ok_or_else(|| {
+                            ModrinthError::NoCompatibleVersion(format!(
+                                "Project `{}` required by {} has no version for {} on {}",
+                                project, version.name, loader, game_version
+                            ))
+                        })?,
+                    (None, None) => continue,
+                };
+
+                queue.push_back(dependency);
+            }
+
+            plan.push(version);
+        }
+
+        let remaining = installed
+            .iter()
+            .filter(|v| !plan.iter().any(|p| p.project_id == v.project_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        check_incompatibilities(&plan, &remaining)?;
+
+        Ok(plan)
+    }
+
+    /// Installs a version and its required dependencies into the `mods` folder of an instance,
+    /// and returns the paths of the written files.
+    ///
+    /// All files are downloaded and verified before any of them is written,
+    /// and the jars of the installed versions they replace are removed afterwards.
+    pub async fn install(
+        &self,
+        version_id: &str,
+        game_version: &str,
+        loader: &str,
+        mods_dir: &Path,
+    ) -> Result<Vec<PathBuf>, ModrinthError> {
+        let version = self.get_version(version_id).await?;
+        let installed = self.installed_versions(mods_dir).await?;
+        let versions = installed
+            .iter()
+            .map(|m| m.version.clone())
+            .collect::<Vec<_>>();
+        let plan = self
+            .resolve(version, game_version, loader, &versions)
+            .await?;
+
+        let mut primary_files = Vec::with_capacity(plan.len());
+
+        // File names come from the API, so they're checked before anything is downloaded.
+        for version in &plan {
+            let Some(file) = primary_file(version) else {
+                return Err(ModrinthError::NotFound(format!(
+                    "The file of {} {}",
+                    version.name, version.version_number
+                )));
+            };
+            if !is_plain_jar_name(&file.filename) {
+                return Err(ModrinthError::InvalidFileName(file.filename.clone()));
+            }
+
+            primary_files.push(file);
+        }
+
+        let mut files = Vec::new();
+
+        for file in primary_files {
+            let bytes = self.send_raw(CLIENT.get(&file.url), &file.url).await?;
+
+            if sha512_of(&bytes) != file.hashes.sha512 {
+                return Err(ModrinthError::CheckIntegrityError(file.filename.clone()));
+            }
+
+            files.push((file.filename.clone(), bytes));
+        }
+
+        let mut paths = Vec::new();
+
+        for (file_name, bytes) in files {
+            file_system::write_into_file(&mods_dir, &file_name, &bytes).await?;
+            paths.push(mods_dir.join(file_name));
+        }
+
+        // A jar which has just been overwritten is not removed.
+        for superseded in installed.iter().filter(|m| {
+            plan.iter().any(|v| v.project_id == m.version.project_id) && !paths.contains(&m.path)
+        }) {
+            tokio::fs::remove_file(&superseded.path).await?;
+        }
+
+        Ok(paths)
+    }
+
+    /// Identifies the jars in the `mods` folder by their SHA-512,
+    /// and returns the ones known by Modrinth.
+    pub async fn installed_versions(
+        &self,
+        mods_dir: &Path,
+    ) -> Result<Vec<InstalledMod>, ModrinthError> {
+        let mut entries = match file_system::read_dir(&mods_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut paths = HashMap::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+
+            if path.extension().is_some_and(|ext| ext == "jar") {
+                paths.insert(sha512_of(&tokio::fs::read(&path).await?), path);
+            }
+        }
+
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let hashes = paths.keys().collect::<Vec<_>>();
+        let versions: HashMap<String, ModrinthVersion> = self
+            .send(
+                CLIENT
+                    .post(format!("{}/version_files", self.api_root))
+                    .json(&json!({ "hashes": hashes, "algorithm": "sha512" })),
+                "version_files",
+            )
+            .await?;
+
+        Ok(versions
+            .into_iter()
+            .filter_map(|(hash, version)| {
+                Some(InstalledMod {
+                    path: paths.remove(&hash)?,
+                    version,
+                })
+            })
+            .collect())
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+        subject: &str,
+    ) -> Result<T, ModrinthError> {
+        let bytes = self.send_raw(request, subject).await?;
+
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn send_raw(
+        &self,
+        request: RequestBuilder,
+        subject: &str,
+    ) -> Result<Vec<u8>, ModrinthError> {
+        let response = request.header(USER_AGENT, USER_AGENT_VALUE).send().await?;
+
+        match response.status() {
+            status if status.is_success() => Ok(response.bytes().await?.to_vec()),
+            StatusCode::NOT_FOUND => Err(ModrinthError::NotFound(subject.to_owned())),
+            status => Err(ModrinthError::InternetError(format!(
+                "{} responded with {}",
+                response.url(),
+                status
+            ))),
+        }
+    }
+}
+
+impl Default for ModrinthClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the primary file of a version, or the first one if none is marked.
+pub fn primary_file(version: &ModrinthVersion) -> Option<&VersionFile> {
+    version
+        .files
+        .iter()
+        .find(|file| file.primary)
+        .or_else(|| version.files.first())
+}
+
+/// Checks whether a file name is a plain jar name, e.g. `sodium-fabric-0.6.13+mc1.21.5.jar`,
+/// which can't point outside the folder it's joined to.
+fn is_plain_jar_name(name: &str) -> bool {
+    name.len() > ".jar".len()
+        && name.ends_with(".jar")
+        && !name.contains(['/', '\\'])
+        && !name.contains("..")
+}
+
+/// Checks the incompatibilities declared by the versions to be installed and the installed ones.
+fn check_incompatibilities(
+    plan: &[ModrinthVersion],
+    installed: &[ModrinthVersion],
+) -> Result<(), ModrinthError> {
+    let all = plan.iter().chain(installed).collect::<Vec<_>>();
+
+    for version in &all {
+        for dependency in &version.dependencies {
+            if dependency.dependency_type != DependencyType::Incompatible {
+                continue;
+            }
+
+            let conflict = all.iter().find(|other| {
+                dependency.version_id.as_ref() == Some(&other.id)
+                    || dependency.project_id.as_ref() == Some(&other.project_id)
+            });
+
+            // Conflicts only among the installed mods are not caused by this installation.
+            if let Some(other) = conflict
+                && (plan.contains(*version) || plan.contains(*other))
+            {
+                return Err(ModrinthError::Incompatible(format!(
+                    "{} {} is incompatible with {} {}",
+                    version.name, version.version_number, other.name, other.version_number
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn sha512_of(bytes: &[u8]) -> String {
+    format!("{:x}", Sha512::digest(bytes))
+}
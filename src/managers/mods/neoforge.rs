@@ -0,0 +1,159 @@
+//! # NeoForge
+//!
+//! Installs NeoForge with its installer jar, which shares the format of Forge,
+//! so the installation goes through the same processors, see [`forge`].
+//!
+//! NeoForge versions are named after the game version without the leading `1.`,
+//! e.g. `21.1.172` is for 1.21.1 and `21.0.167` is for 1.21.
+//! The only exception is 1.20.1, where NeoForge was still published as `net.neoforged:forge`
+//! with Forge's numbering, e.g. `1.20.1-47.1.106`.
+//!
+//! [`forge`]: crate::managers::mods::forge
+
+use crate::constants::{BANGBANG93, Category, DownloadSource};
+use crate::error_handling::{DownloadError, InstallError};
+use crate::managers::mods::{self, LoaderVersion, forge};
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+const NEOFORGE_MAVEN: &str = "https://maven.neoforged.net/releases";
+/// The only game version NeoForge supports with the artifact forked from Forge.
+const LEGACY_GAME_VERSION: &str = "1.20.1";
+
+static VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<version>([^<]+)</version>").unwrap());
+
+pub struct NeoForgeInstaller {
+    game_version: String,
+    neoforge_version: String,
+    source: DownloadSource,
+    category: Category,
+}
+
+impl NeoForgeInstaller {
+    /// `neoforge_version` is listed by [`list_versions`], e.g. `21.1.172` or `47.1.106` for 1.20.1.
+    pub fn new(
+        game_version: &str,
+        neoforge_version: &str,
+        src: DownloadSource,
+        category: Category,
+    ) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            neoforge_version: neoforge_version.to_string(),
+            source: src,
+            category,
+        }
+    }
+
+    /// Downloads the installer, installs NeoForge with it,
+    /// and returns the ID of the installed version, e.g. `neoforge-21.1.172`.
+    ///
+    /// The server version is suffixed with `-server`, so that it doesn't replace the client one.
+    pub async fn install(&self) -> Result<String, InstallError> {
+        let coordinate = if self.game_version == LEGACY_GAME_VERSION {
+            format!(
+                "net.neoforged:forge:{}-{}:installer",
+                LEGACY_GAME_VERSION, self.neoforge_version
+            )
+        } else {
+            format!("net.neoforged:neoforge:{}:installer", self.neoforge_version)
+        };
+        let installer = mods::download_installer(&repository(self.source), &coordinate).await?;
+
+        forge::install_with_installer(&installer, self.source, self.category).await
+    }
+}
+
+/// Lists the NeoForge versions of a game version, from the newest to the oldest.
+pub async fn list_versions(
+    game_version: &str,
+    src: DownloadSource,
+) -> Result<Vec<LoaderVersion>, DownloadError> {
+    let artifact = if game_version == LEGACY_GAME_VERSION {
+        "forge"
+    } else {
+        "neoforge"
+    };
+    let metadata = mods::request_text(&format!(
+        "{}/net/neoforged/{}/maven-metadata.xml",
+        repository(src),
+        artifact
+    ))
+    .await?;
+
+    Ok(versions_from(&metadata, game_version))
+}
+
+/// Picks the versions of a game version from `maven-metadata.xml`, e.g.
+///
+/// ```xml
+/// <metadata>
+///     <versioning>
+///         <versions>
+///             <version>21.1.171</version>
+///             <version>21.1.172</version>
+///             <version>21.5.0-beta</version>
+///         </versions>
+///     </versioning>
+/// </metadata>
+/// ```
+///
+/// Versions with suffixes like `-beta` are not stable.
+pub fn versions_from(metadata: &str, game_version: &str) -> Vec<LoaderVersion> {
+    let Some(prefix) = version_prefix(game_version) else {
+        return Vec::new();
+    };
+    let mut versions = VERSION
+        .captures_iter(metadata)
+        .filter_map(|caps| {
+            let version = caps[1].strip_prefix(&prefix)?;
+
+            Some(LoaderVersion {
+                version: if game_version == LEGACY_GAME_VERSION {
+                    version.to_string()
+                } else {
+                    caps[1].to_string()
+                },
+                stable: !version.contains('-'),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Maven lists versions from the oldest to the newest.
+    versions.reverse();
+
+    versions
+}
+
+/// Maps a game version to the prefix of its NeoForge versions,
+/// e.g. `21.1.` for 1.21.1, `21.0.` for 1.21, and `1.20.1-` for 1.20.1.
+///
+/// Returns `None` for the versions that NeoForge doesn't support, i.e. the ones before 1.20.1.
+pub fn version_prefix(game_version: &str) -> Option<String> {
+    if game_version == LEGACY_GAME_VERSION {
+        return Some(format!("{}-", LEGACY_GAME_VERSION));
+    }
+
+    let mut parts = game_version.strip_prefix("1.")?.split('.');
+    let minor = parts.next()?.parse::<u32>().ok()?;
+    let patch = match parts.next() {
+        Some(patch) => patch.parse::<u32>().ok()?,
+        None => 0,
+    };
+
+    if minor < 20 || parts.next().is_some() {
+        return None;
+    }
+
+    Some(format!("{}.{}.", minor, patch))
+}
+
+fn repository(src: DownloadSource) -> String {
+    match src {
+        DownloadSource::Official => NEOFORGE_MAVEN.to_string(),
+        DownloadSource::Bangbang93 => format!("{}/maven", BANGBANG93),
+    }
+}
@@ -0,0 +1,290 @@
+//! # OptiFine
+//!
+//! Installs OptiFine, which is loaded by LaunchWrapper as a tweaker,
+//! on top of a vanilla version or a legacy Forge version.
+//!
+//! OptiFine has no download API, so its jar is either downloaded from BMCLAPI,
+//! or provided by the user, e.g. downloaded from the official site manually.
+//! Newer jars ship a patcher which builds the library from the vanilla jar,
+//! so the vanilla version must be downloaded before installing.
+//!
+//! Since 1.13, Forge no longer uses LaunchWrapper, and OptiFine is loaded as a mod there.
+
+use crate::constants::{BANGBANG93, DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::{DownloadError, InstallError};
+use crate::file_system;
+use crate::managers::mods::forge::{self, Installer};
+use crate::managers::mods::{self, LoaderVersion};
+use crate::managers::version;
+use crate::utils::downloader::{CLIENT, Downloader, FileInfo};
+
+use std::borrow::Cow;
+use std::env;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+use tokio::process::Command;
+use zip::ZipArchive;
+
+const TWEAKER: &str = "optifine.OptiFineTweaker";
+const FORGE_TWEAKER: &str = "optifine.OptiFineForgeTweaker";
+/// Used when the jar doesn't bundle its own LaunchWrapper.
+const LAUNCH_WRAPPER_LIBRARY: &str = "net.minecraft:launchwrapper:1.12";
+
+pub struct OptiFineInstaller {
+    game_version: String,
+    optifine_version: String,
+    parent: String,
+    source: DownloadSource,
+}
+
+impl OptiFineInstaller {
+    /// `optifine_version` is listed by [`list_versions`], e.g. `HD_U_G5`.
+    ///
+    /// `parent` is the installed version which OptiFine is installed on,
+    /// e.g. `1.12.2` or `1.12.2-forge-14.23.5.2860`.
+    pub fn new(
+        game_version: &str,
+        optifine_version: &str,
+        parent: &str,
+        src: DownloadSource,
+    ) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            optifine_version: optifine_version.to_string(),
+            parent: parent.to_string(),
+            source: src,
+        }
+    }
+
+    /// Downloads the jar from BMCLAPI, installs OptiFine with it,
+    /// and returns the ID of the installed version, e.g. `1.12.2-optifine-HD_U_G5`.
+    ///
+    /// The official source doesn't provide the jar, so it must be installed by [`install_from_jar`].
+    ///
+    /// [`install_from_jar`]: Self::install_from_jar
+    pub async fn install(&self) -> Result<String, InstallError> {
+        let DownloadSource::Bangbang93 = self.source else {
+            return Err(InstallError::DownloadError(
+                "OptiFine can only be downloaded from BMCLAPI, or installed from a provided jar"
+                    .to_string(),
+            ));
+        };
+        let Some((edition, patch)) = split_version(&self.optifine_version) else {
+            return Err(InstallError::DownloadError(format!(
+                "Invalid OptiFine version `{}`",
+                self.optifine_version
+            )));
+        };
+
+        let temp = env::temp_dir().join(format!("gridcore-optifine-{}", std::process::id()));
+        let file_name = format!(
+            "OptiFine_{}_{}.jar",
+            self.game_version, self.optifine_version
+        );
+        let file_info = FileInfo {
+            path: Cow::from(temp.as_path()),
+            name: Cow::from(file_name.as_str()),
+            url: format!(
+                "{}/optifine/{}/{}/{}",
+                BANGBANG93, self.game_version, edition, patch
+            )
+            .into(),
+            sha1: None,
+        };
+        let downloader = Downloader::new(&CLIENT, file_info);
+        downloader.download_file().await?;
+
+        let result = self.install_from_jar(&temp.join(&file_name)).await;
+        let _ = tokio::fs::remove_dir_all(&temp).await;
+
+        result
+    }
+
+    /// Installs OptiFine with a local jar, and returns the ID of the installed version.
+    pub async fn install_from_jar(&self, jar: &Path) -> Result<String, InstallError> {
+        let parent = version::read_version_json(&self.parent)
+            .await
+            .map_err(DownloadError::from)?;
+        let mut archive = ZipArchive::new(Cursor::new(tokio::fs::read(jar).await?))?;
+
+        let target = library(&format!(
+            "optifine:OptiFine:{}_{}",
+            self.game_version, self.optifine_version
+        ))?;
+        if let Some(dir) = target.parent() {
+            file_system::create_dir(&dir).await?;
+        }
+
+        if archive.by_name("optifine/Patcher.class").is_ok() {
+            self.patch(jar, &target).await?;
+        } else {
+            tokio::fs::copy(jar, &target).await?;
+        }
+
+        let launchwrapper = read_text_entry(&mut archive, "launchwrapper-of.txt")?;
+        if let Some(version) = &launchwrapper
+            && !forge::extract_entry(
+                &mut archive,
+                &format!("launchwrapper-of-{}.jar", version),
+                &library(&format!("optifine:launchwrapper-of:{}", version))?,
+            )
+            .await?
+        {
+            return Err(InstallError::InvalidInstaller(format!(
+                "LaunchWrapper {} is not in the jar",
+                version
+            )));
+        }
+
+        let data = version_json_from(
+            &parent,
+            &self.game_version,
+            &self.optifine_version,
+            launchwrapper.as_deref(),
+        )?;
+
+        mods::download_maven_libraries(&data, self.source).await?;
+        Ok(mods::write_version_json(MINECRAFT_ROOT, &data).await?)
+    }
+
+    /// Runs the patcher in the jar, which writes the library built from the vanilla jar.
+    async fn patch(&self, jar: &Path, target: &Path) -> Result<(), InstallError> {
+        let game_jar = PathBuf::from(format!(
+            "{}/versions/{}/{}.jar",
+            MINECRAFT_ROOT, self.game_version, self.game_version
+        ));
+
+        if !game_jar.exists() {
+            return Err(InstallError::ProcessorError(format!(
+                "Minecraft {} must be downloaded before installing",
+                self.game_version
+            )));
+        }
+
+        let status = Command::new("java")
+            .arg("-cp")
+            .arg(jar)
+            .arg("optifine.Patcher")
+            .arg(&game_jar)
+            .arg(jar)
+            .arg(target)
+            .status()
+            .await?;
+
+        if !status.success() {
+            return Err(InstallError::ProcessorError(format!(
+                "The patcher of OptiFine exited with {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Lists the OptiFine versions of a game version, which are only provided by BMCLAPI.
+pub async fn list_versions(game_version: &str) -> Result<Vec<LoaderVersion>, DownloadError> {
+    let data = mods::request_json(&format!("{}/optifine/{}", BANGBANG93, game_version)).await?;
+
+    Ok(versions_from(&data))
+}
+
+/// Parses the versions listed by BMCLAPI, e.g.
+/// `[{"mcversion": "1.12.2", "type": "HD_U", "patch": "G5", "filename": "OptiFine_1.12.2_HD_U_G5.jar"}]`.
+///
+/// Preview versions, e.g. `HD_U_H1_pre1`, are not stable.
+pub fn versions_from(data: &Value) -> Vec<LoaderVersion> {
+    data.as_array()
+        .map(|versions| {
+            versions
+                .iter()
+                .filter_map(|v| {
+                    let (edition, patch) = (v["type"].as_str()?, v["patch"].as_str()?);
+
+                    Some(LoaderVersion {
+                        version: format!("{}_{}", edition, patch),
+                        stable: !patch.contains("pre"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generates the version JSON of an OptiFine version, which inherits from `parent`,
+/// i.e. the merged JSON of the version which OptiFine is installed on.
+///
+/// `launchwrapper` is the version of LaunchWrapper bundled in the jar, if any.
+pub fn version_json_from(
+    parent: &Value,
+    game_version: &str,
+    optifine_version: &str,
+    launchwrapper: Option<&str>,
+) -> Result<Value, InstallError> {
+    let forge = parent["libraries"].as_array().is_some_and(|libs| {
+        libs.iter()
+            .filter_map(|lib| lib["name"].as_str())
+            .any(|name| {
+                name.starts_with("net.minecraftforge:forge:")
+                    || name.starts_with("net.minecraftforge:minecraftforge:")
+            })
+    });
+
+    if forge && parent["mainClass"] != mods::LAUNCH_WRAPPER {
+        return Err(InstallError::InvalidInstaller(
+            "OptiFine is loaded as a mod by this Forge version, so it should be put into `mods`"
+                .to_string(),
+        ));
+    }
+
+    let mut libraries = vec![json!({
+        "name": format!("optifine:OptiFine:{}_{}", game_version, optifine_version)
+    })];
+
+    // Forge already loads LaunchWrapper.
+    match (forge, launchwrapper) {
+        (true, _) => {}
+        (false, Some(version)) => {
+            libraries.push(json!({ "name": format!("optifine:launchwrapper-of:{}", version) }))
+        }
+        (false, None) => libraries.push(json!({
+            "name": LAUNCH_WRAPPER_LIBRARY,
+            "url": mods::MOJANG_LIBRARIES
+        })),
+    }
+
+    Ok(mods::tweaker_version_json(
+        parent,
+        &format!("optifine-{}", optifine_version),
+        if forge { FORGE_TWEAKER } else { TWEAKER },
+        libraries,
+    )?)
+}
+
+/// Splits a version into the edition and the patch, e.g. `HD_U` and `G5` for `HD_U_G5`.
+fn split_version(version: &str) -> Option<(&str, &str)> {
+    let (index, _) = version.match_indices('_').nth(1)?;
+
+    Some((&version[..index], &version[index + 1..]))
+}
+
+fn library(name: &str) -> Result<PathBuf, InstallError> {
+    mods::library_path(MINECRAFT_ROOT, name).ok_or_else(|| {
+        InstallError::InvalidInstaller(format!("Invalid Maven coordinate `{}`", name))
+    })
+}
+
+/// Reads a text entry of the jar, and returns `None` if it doesn't exist.
+fn read_text_entry(archive: &mut Installer, name: &str) -> Result<Option<String>, InstallError> {
+    let mut contents = String::new();
+
+    match archive.by_name(name) {
+        Ok(mut entry) => entry.read_to_string(&mut contents)?,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(Some(contents.trim().to_owned()))
+}
@@ -0,0 +1,155 @@
+//! # Quilt
+//!
+//! Installs Quilt loader with its meta service.
+//!
+//! Unlike Fabric, the version JSON is generated here from the launcher metadata of the loader,
+//! so that the game runs with Quilt's hashed mappings.
+//! Quilt isn't mirrored by BMCLAPI, so it's always installed from the official sources.
+//!
+//! The vanilla version must be downloaded by [`MinecraftDownloader`] as well,
+//! since the Quilt version inherits from it.
+//!
+//! [`MinecraftDownloader`]: crate::managers::game::download::MinecraftDownloader
+
+use crate::constants::{Category, DownloadSource, MINECRAFT_ROOT};
+use crate::error_handling::DownloadError;
+use crate::managers::mods::{self, LoaderVersion};
+
+use std::time::SystemTime;
+
+use serde_json::{Value, json};
+
+const QUILT_META: &str = "https://meta.quiltmc.org";
+const QUILT_MAVEN: &str = "https://maven.quiltmc.org/repository/release/";
+
+pub struct QuiltInstaller {
+    game_version: String,
+    loader_version: String,
+    category: Category,
+}
+
+impl QuiltInstaller {
+    pub fn new(game_version: &str, loader_version: &str, category: Category) -> Self {
+        Self {
+            game_version: game_version.to_string(),
+            loader_version: loader_version.to_string(),
+            category,
+        }
+    }
+
+    /// Writes the version JSON and downloads the libraries of Quilt,
+    /// and returns the ID of the installed version, e.g. `quilt-loader-0.29.0-1.21.5`.
+    ///
+    /// The server version is suffixed with `-server`, so that it doesn't replace the client one.
+    pub async fn install(&self) -> Result<String, DownloadError> {
+        let metadata = mods::request_json(&format!(
+            "{}/v3/versions/loader/{}/{}",
+            QUILT_META, self.game_version, self.loader_version
+        ))
+        .await?;
+
+        let data = version_json_from(&metadata, &self.game_version, self.category)?;
+
+        mods::download_maven_libraries(&data, DownloadSource::Official).await?;
+        mods::write_version_json(MINECRAFT_ROOT, &data).await
+    }
+}
+
+/// Lists the Quilt loader versions that support a game version, from the newest to the oldest.
+pub async fn list_loader_versions(game_version: &str) -> Result<Vec<LoaderVersion>, DownloadError> {
+    let data = mods::request_json(&format!(
+        "{}/v3/versions/loader/{}",
+        QUILT_META, game_version
+    ))
+    .await?;
+
+    Ok(loader_versions_from(&data))
+}
+
+/// Parses the loader versions of a game version.
+///
+/// Quilt doesn't mark stable versions, so the ones without pre-release suffixes,
+/// e.g. `0.29.0-beta.3`, are treated as stable.
+pub fn loader_versions_from(data: &Value) -> Vec<LoaderVersion> {
+    data.as_array()
+        .map(|versions| {
+            versions
+                .iter()
+                .filter_map(|v| {
+                    let version = v["loader"]["version"].as_str()?;
+
+                    Some(LoaderVersion {
+                        version: version.to_string(),
+                        stable: !version.contains('-'),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generates the version JSON from the metadata of a loader version, e.g.
+///
+/// ```json
+/// {
+///     "loader": { "maven": "org.quiltmc:quilt-loader:0.29.0", "version": "0.29.0" },
+///     "hashed": { "maven": "org.quiltmc:hashed:1.21.5", "version": "1.21.5" },
+///     "intermediary": { "maven": "net.fabricmc:intermediary:1.21.5", "version": "1.21.5" },
+///     "launcherMeta": {
+///         "libraries": { "client": [], "common": [{ "name": "...", "url": "..." }], "server": [] },
+///         "mainClass": { "client": "org.quiltmc.loader.impl.launch.knot.KnotClient", "server": "..." }
+///     }
+/// }
+/// ```
+pub fn version_json_from(
+    metadata: &Value,
+    game_version: &str,
+    category: Category,
+) -> Result<Value, DownloadError> {
+    let (Value::String(loader_version), Value::String(loader), Value::String(hashed)) = (
+        &metadata["loader"]["version"],
+        &metadata["loader"]["maven"],
+        &metadata["hashed"]["maven"],
+    ) else {
+        return Err(DownloadError::JsonError(format!(
+            "Quilt loader metadata for {} is incomplete",
+            game_version
+        )));
+    };
+
+    let side = category.to_string();
+    let Value::String(main_class) = &metadata["launcherMeta"]["mainClass"][&side] else {
+        return Err(DownloadError::JsonError(format!(
+            "Quilt loader {} doesn't declare the main class of {}",
+            loader_version, side
+        )));
+    };
+
+    let mut libraries = Vec::new();
+
+    for group in ["common", side.as_str()] {
+        if let Value::Array(libs) = &metadata["launcherMeta"]["libraries"][group] {
+            libraries.extend(libs.iter().cloned());
+        }
+    }
+
+    libraries.push(json!({ "name": hashed, "url": QUILT_MAVEN }));
+    libraries.push(json!({ "name": loader, "url": QUILT_MAVEN }));
+
+    let id = match category {
+        Category::Client => format!("quilt-loader-{}-{}", loader_version, game_version),
+        Category::Server => format!("quilt-loader-{}-{}-server", loader_version, game_version),
+    };
+    let time = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+
+    Ok(json!({
+        "id": id,
+        "inheritsFrom": game_version,
+        "type": "release",
+        "time": time,
+        "releaseTime": time,
+        "mainClass": main_class,
+        "arguments": { "game": [], "jvm": [] },
+        "libraries": libraries
+    }))
+}
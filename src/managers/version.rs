@@ -2,7 +2,8 @@ use crate::constants::MINECRAFT_ROOT;
 use crate::error_handling::JsonError;
 use crate::utils::json_processer;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io;
 
 use serde_json::{Map, Value};
 
@@ -26,3 +27,143 @@ pub(crate) async fn read_version_manifest() -> Result<HashMap<String, Map<String
 
     Ok(manifest)
 }
+
+/// Reads the JSON of a version, e.g. `versions/1.21.5/1.21.5.json`.
+///
+/// If the version inherits from another one, like the versions of mod loaders do,
+/// it's merged with all of its ancestors, see [`merge_version_json`].
+pub async fn read_version_json(id: &str) -> Result<Value, JsonError> {
+    let mut data = read_local_version_json(id).await?;
+    let mut descendants = Vec::new();
+    let mut visited = HashSet::from([id.to_owned()]);
+
+    while let Value::String(parent) = &data["inheritsFrom"] {
+        if !visited.insert(parent.to_owned()) {
+            return Err(JsonError::JsonParseError(serde::de::Error::custom(
+                format!("Version `{}` inherits from itself", parent),
+            )));
+        }
+
+        let parent = read_local_version_json(parent).await?;
+        descendants.push(data);
+        data = parent;
+    }
+
+    Ok(descendants
+        .into_iter()
+        .rev()
+        .fold(data, |parent, child| merge_version_json(parent, &child)))
+}
+
+/// Returns the version that a version inherits from, if it's installed locally and does.
+pub async fn inherits_from(id: &str) -> Result<Option<String>, JsonError> {
+    match read_local_version_json(id).await {
+        Ok(data) => Ok(data["inheritsFrom"]
+            .as_str()
+            .map(|parent| parent.to_owned())),
+        Err(JsonError::FileSystemError(e)) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Merges the JSON of a version into the one it inherits from.
+///
+/// - Libraries of the child come first, and replace the ones of the parent
+///   with the same group, artifact and classifier.
+/// - Game and JVM arguments of the child are appended to the parent's ones.
+/// - Other fields of the child, e.g. `id` and `mainClass`, replace the parent's ones.
+/// - The child runs the game jar of the parent, which is kept in `jar`,
+///   unless it declares its own.
+pub fn merge_version_json(parent: Value, child: &Value) -> Value {
+    let (Value::Object(mut merged), Value::Object(child)) = (parent, child) else {
+        return child.clone();
+    };
+    let jar = merged.get("jar").or(merged.get("id")).cloned();
+
+    for (key, value) in child {
+        match key.as_str() {
+            "inheritsFrom" => {}
+            "libraries" => {
+                let Value::Array(libs) = value else {
+                    continue;
+                };
+                let overridden = libs
+                    .iter()
+                    .filter_map(|lib| library_key(&lib["name"]))
+                    .collect::<HashSet<_>>();
+
+                let mut libraries = libs.clone();
+
+                if let Some(Value::Array(parent_libs)) = merged.get("libraries") {
+                    libraries.extend(
+                        parent_libs
+                            .iter()
+                            .filter(|lib| {
+                                library_key(&lib["name"])
+                                    .is_none_or(|key| !overridden.contains(&key))
+                            })
+                            .cloned(),
+                    );
+                }
+
+                merged.insert(key.to_owned(), Value::Array(libraries));
+            }
+            "arguments" => {
+                let mut arguments = merged
+                    .get("arguments")
+                    .cloned()
+                    .unwrap_or(Value::Object(Map::new()));
+
+                if let Value::Object(args) = value {
+                    for (arg_ty, arg_val) in args {
+                        let Value::Array(arg_val) = arg_val else {
+                            continue;
+                        };
+
+                        match &mut arguments[arg_ty] {
+                            Value::Array(target) => target.extend(arg_val.iter().cloned()),
+                            target => *target = Value::Array(arg_val.clone()),
+                        }
+                    }
+                }
+
+                merged.insert(key.to_owned(), arguments);
+            }
+            _ => {
+                merged.insert(key.to_owned(), value.clone());
+            }
+        }
+    }
+
+    merged.remove("inheritsFrom");
+
+    if !child.contains_key("jar")
+        && let Some(jar) = jar
+    {
+        merged.insert("jar".to_owned(), jar);
+    }
+
+    Value::Object(merged)
+}
+
+async fn read_local_version_json(id: &str) -> Result<Value, JsonError> {
+    json_processer::read(
+        &format!("{}/versions/{}", MINECRAFT_ROOT, id),
+        &format!("{}.json", id),
+    )
+    .await
+}
+
+/// Identifies a library by its Maven coordinate without the version,
+/// e.g. `org.ow2.asm:asm` for `org.ow2.asm:asm:9.8`.
+fn library_key(name: &Value) -> Option<String> {
+    let parts = name.as_str()?.split(':').collect::<Vec<_>>();
+
+    match parts.as_slice() {
+        [group, artifact, _] => Some(format!("{}:{}", group, artifact)),
+        [group, artifact, _, classifier, ..] => {
+            Some(format!("{}:{}:{}", group, artifact, classifier))
+        }
+        _ => None,
+    }
+}
@@ -1,5 +1,6 @@
 pub mod decompresser;
 pub mod downloader;
 pub mod json_processer;
+pub mod maven;
 pub mod request_handler;
 pub mod sha1_checker;
@@ -10,6 +10,9 @@ use std::path::Path;
 
 use zip::ZipArchive;
 
+/// The extensions of native libraries on Windows, Linux and macOS.
+const NATIVE_EXTENSIONS: [&str; 4] = [".dll", ".so", ".dylib", ".jnilib"];
+
 pub async fn decompress_file<P: AsRef<Path>>(
     file_path: &P,
     file_name: &str,
@@ -29,8 +32,10 @@ pub async fn decompress_file<P: AsRef<Path>>(
         if let Some(file_name) = out_path.file_name() {
             let file_name = file_name.to_str().unwrap();
 
-            // TODO: Add support for Linux and MacOS platform.
-            if file_name.ends_with(".dll") {
+            if NATIVE_EXTENSIONS
+                .iter()
+                .any(|extension| file_name.ends_with(extension))
+            {
                 let mut buffer = Vec::new();
                 file.read_to_end(&mut buffer)?;
                 file_system::write_into_file(extract_path, file_name, &buffer).await?;
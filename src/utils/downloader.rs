@@ -53,15 +53,12 @@ impl<'d> Downloader<'d> {
         // 1. The target file exists.
         // 2. Its corresponding SHA1 value is equal to the provided one.
         // Note: If `sha1` doesn't exist, then the second condition will be omitted.
-        // HACK: Nested `if` expressions! There might has a better method to do so.
-        if self.file_info.path.join(&*self.file_info.name).exists() {
-            if let Some(sha1) = &self.file_info.sha1 {
-                if &sha1_checker::calculate_sha1(&self.file_info.path, &self.file_info.name).await?
-                    == sha1
-                {
-                    return Ok(());
-                }
-            }
+        if self.file_info.path.join(&*self.file_info.name).exists()
+            && let Some(sha1) = &self.file_info.sha1
+            && &sha1_checker::calculate_sha1(&self.file_info.path, &self.file_info.name).await?
+                == sha1
+        {
+            return Ok(());
         }
 
         self.download_file_inner().await?;
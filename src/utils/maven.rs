@@ -0,0 +1,179 @@
+//! # Maven
+//!
+//! Parses Maven coordinates, and resolves artifacts against Maven repositories.
+
+use crate::constants::{BANGBANG93, DownloadSource};
+use crate::error_handling::DownloadError;
+use crate::utils::downloader::{self, CLIENT};
+
+use std::fmt::Display;
+
+/// The repositories searched in order for libraries which don't declare their own.
+pub const DEFAULT_REPOSITORIES: [&str; 5] = [
+    "https://libraries.minecraft.net/",
+    "https://maven.minecraftforge.net/",
+    "https://maven.neoforged.net/releases/",
+    "https://maven.fabricmc.net/",
+    "https://maven.quiltmc.org/repository/release/",
+];
+/// The repositories mirrored by BMCLAPI.
+///
+/// Legacy Forge installers still refer to Forge's former repository in `files.minecraftforge.net`,
+/// and LiteLoader is published in two repositories, which BMCLAPI merges into one.
+const MIRRORED_REPOSITORIES: [&str; 8] = [
+    "https://libraries.minecraft.net/",
+    "https://maven.minecraftforge.net/",
+    "http://files.minecraftforge.net/maven/",
+    "https://files.minecraftforge.net/maven/",
+    "https://maven.neoforged.net/releases/",
+    "https://maven.fabricmc.net/",
+    "http://dl.liteloader.com/versions/",
+    "http://repo.mumfrey.com/content/repositories/snapshots/",
+];
+
+/// A Maven coordinate `group:artifact:version[:classifier][@extension]`,
+/// e.g. `org.lwjgl:lwjgl:3.3.3:natives-linux`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MavenCoordinate {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+    /// `jar` if it's omitted.
+    pub extension: String,
+}
+
+/// An artifact found in a repository.
+#[derive(Debug, PartialEq)]
+pub struct ResolvedArtifact {
+    pub url: String,
+    /// The SHA1 published along with the artifact, if any.
+    pub sha1: Option<String>,
+}
+
+impl MavenCoordinate {
+    /// Returns `None` if any part is missing or empty.
+    pub fn parse(name: &str) -> Option<Self> {
+        let (coordinate, extension) = name.split_once('@').unwrap_or((name, "jar"));
+        let parts = coordinate.split(':').collect::<Vec<_>>();
+
+        if extension.is_empty() || parts.iter().any(|part| part.is_empty()) {
+            return None;
+        }
+
+        let (group, artifact, version, classifier) = match parts.as_slice() {
+            [group, artifact, version] => (group, artifact, version, None),
+            [group, artifact, version, classifier] => {
+                (group, artifact, version, Some(classifier.to_string()))
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            group: group.to_string(),
+            artifact: artifact.to_string(),
+            version: version.to_string(),
+            classifier,
+            extension: extension.to_string(),
+        })
+    }
+
+    /// Returns the directory of the artifact in a repository, e.g. `net/fabricmc/fabric-loader/0.16.14`.
+    pub fn directory(&self) -> String {
+        format!(
+            "{}/{}/{}",
+            self.group.replace('.', "/"),
+            self.artifact,
+            self.version
+        )
+    }
+
+    /// Returns the file name of the artifact, e.g. `lwjgl-3.3.3-natives-linux.jar`.
+    pub fn file_name(&self) -> String {
+        match &self.classifier {
+            Some(classifier) => format!(
+                "{}-{}-{}.{}",
+                self.artifact, self.version, classifier, self.extension
+            ),
+            None => format!("{}-{}.{}", self.artifact, self.version, self.extension),
+        }
+    }
+
+    /// Returns the path of the artifact in a repository,
+    /// e.g. `net/fabricmc/fabric-loader/0.16.14/fabric-loader-0.16.14.jar`.
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.directory(), self.file_name())
+    }
+
+    /// Returns the URL of the artifact in a repository.
+    pub fn url(&self, repository: &str) -> String {
+        format!("{}/{}", repository.trim_end_matches('/'), self.path())
+    }
+}
+
+impl Display for MavenCoordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)?;
+
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{}", classifier)?;
+        }
+        if self.extension != "jar" {
+            write!(f, "@{}", self.extension)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the first repository which has the artifact,
+/// i.e. which publishes the `.sha1` file along with it.
+///
+/// Unreachable repositories are skipped, and `None` is returned if no repository has the artifact.
+pub async fn resolve(
+    coordinate: &MavenCoordinate,
+    repositories: &[String],
+) -> Option<ResolvedArtifact> {
+    for repository in repositories {
+        let url = coordinate.url(repository);
+
+        if let Ok(Some(sha1)) = request_sha1(&url).await {
+            return Some(ResolvedArtifact {
+                url,
+                sha1: Some(sha1),
+            });
+        }
+    }
+
+    None
+}
+
+/// Requests the `.sha1` file published along with an artifact,
+/// and returns `None` if the repository doesn't publish it.
+pub async fn request_sha1(url: &str) -> Result<Option<String>, DownloadError> {
+    let response = downloader::get_file_from_remote(&CLIENT, &format!("{}.sha1", url)).await?;
+
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    // Some repositories append the file name after the hash.
+    let text = response.text().await?;
+    let sha1 = text.split_whitespace().next().unwrap_or_default();
+
+    let valid = sha1.len() == 40 && sha1.chars().all(|c| c.is_ascii_hexdigit());
+
+    Ok(valid.then(|| sha1.to_ascii_lowercase()))
+}
+
+/// Points the URL of an artifact to BMCLAPI if it's selected and mirrors the repository.
+pub fn mirror_url(url: &str, src: DownloadSource) -> String {
+    match src {
+        DownloadSource::Official => url.to_owned(),
+        DownloadSource::Bangbang93 => MIRRORED_REPOSITORIES
+            .iter()
+            .find_map(|repository| url.strip_prefix(repository))
+            .map(|path| format!("{}/maven/{}", BANGBANG93, path))
+            .unwrap_or_else(|| url.to_owned()),
+    }
+}
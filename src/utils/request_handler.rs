@@ -1,73 +1,81 @@
-use std::any::Any;
 use std::collections::HashMap;
 
-use reqwest::Client;
 use reqwest::header::HeaderMap;
+use reqwest::multipart::Form;
+use reqwest::{Client, Method, Response};
 use serde_json::Value;
 
-// TODO: Should this be separated into two functions?
-/// Sends POST request and receive response.
+/// Sends POST request with a JSON load and receive response.
+///
+/// The status of the response is not checked, since the body may explain the error.
 pub(crate) async fn send_post_request(
     url: &str,
     headers: Option<HeaderMap>,
-    load: &dyn Any,
+    load: &Value,
+) -> Result<Response, reqwest::Error> {
+    Client::new()
+        .post(url)
+        .headers(headers.unwrap_or_default())
+        .json(load)
+        .send()
+        .await
+}
+
+/// Sends POST request with a form load and receive response.
+///
+/// `Content-Type` header is always `application/x-www-form-urlencoded`.
+pub(crate) async fn send_form_request(
+    url: &str,
+    load: &HashMap<&str, &str>,
 ) -> Result<String, reqwest::Error> {
-    match headers {
-        Some(headers) if load.is::<Value>() => {
-            Client::new()
-                .post(url)
-                .headers(headers)
-                // We already identified that `load` is `Value`.
-                .json(load.downcast_ref::<Value>().unwrap())
-                .send()
-                .await?
-                .text()
-                .await
-        }
-        Some(headers) if load.is::<HashMap<&str, &str>>() => {
-            Client::new()
-                .post(url)
-                .headers(headers)
-                // We already identified that `load` is `HashMap<&str, &str>`.
-                .form(load.downcast_ref::<HashMap<&str, &str>>().unwrap())
-                .send()
-                .await?
-                .text()
-                .await
-        }
-        None if load.is::<Value>() => {
-            Client::new()
-                .post(url)
-                // We already identified that `load` is `Value`.
-                .json(load.downcast_ref::<Value>().unwrap())
-                .send()
-                .await?
-                .text()
-                .await
-        }
-        None if load.is::<HashMap<&str, &str>>() => {
-            Client::new()
-                .post(url)
-                // We already identified that `load` is `HashMap<&str, &str>`.
-                .form(load.downcast_ref::<HashMap<&str, &str>>().unwrap())
-                .send()
-                .await?
-                .text()
-                .await
-        }
-        // The `load` is either `Value` or `HashMap<&str, &str>`,
-        // so other cases will never reach out.
-        _ => unreachable!(),
-    }
+    Client::new()
+        .post(url)
+        .form(load)
+        .send()
+        .await?
+        .text()
+        .await
 }
 
 /// Sends GET request and receive response.
-pub(crate) async fn send_get_request(url: &str, token: &str) -> Result<String, reqwest::Error> {
+///
+/// The status of the response is not checked, since the body may explain the error.
+pub(crate) async fn send_get_request(url: &str, token: &str) -> Result<Response, reqwest::Error> {
+    Client::new().get(url).bearer_auth(token).send().await
+}
+
+/// Sends a request with a bearer token and an optional JSON load, e.g. PUT or DELETE,
+/// and receive response.
+///
+/// The status of the response is not checked, since the body may explain the error.
+pub(crate) async fn send_request(
+    method: Method,
+    url: &str,
+    token: &str,
+    load: Option<&Value>,
+) -> Result<Response, reqwest::Error> {
+    let request = Client::new().request(method, url).bearer_auth(token);
+
+    match load {
+        Some(load) => request.json(load),
+        None => request,
+    }
+    .send()
+    .await
+}
+
+/// Sends POST request with a bearer token and a multipart form, and receive response.
+///
+/// The status of the response is not checked, since the body may explain the error.
+pub(crate) async fn send_multipart_request(
+    url: &str,
+    token: &str,
+    form: Form,
+) -> Result<Response, reqwest::Error> {
     Client::new()
-        .get(url)
+        .post(url)
         .bearer_auth(token)
+        .multipart(form)
         .send()
-        .await?
-        .text()
         .await
 }
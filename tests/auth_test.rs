@@ -1,34 +1,684 @@
-use gridcore::auth::{self, MinecraftProfile};
-use gridcore::constants::CONFIG_DIRECTORY;
+mod common;
+
+use common::{Request, Response};
+use gridcore::auth::entitlements::{Entitlements, JavaOwnership};
+use gridcore::auth::loopback::AuthorizationRequest;
+use gridcore::auth::skin::SkinVariant;
+use gridcore::auth::store::{AccountStore, StoreKey};
+use gridcore::auth::{self, AccountType, MicrosoftEndpoints, MinecraftProfile, offline};
+use gridcore::constants::{ACCOUNTS_FILE_NAME, CONFIG_DIRECTORY};
+use gridcore::error_handling::{AuthError, XboxError};
 use gridcore::utils::json_processer;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::fs;
+use tokio::net::TcpStream;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use reqwest::Url;
+use serde_json::{Value, json};
+
 #[ignore = "This test case must be manually tested on local machine."]
 #[tokio::test]
 async fn login_test() {
-    // Assume there is a string that contains a Microsoft authorization code.
-    let auth_code = "";
+    let request = AuthorizationRequest::start().await.unwrap();
+    println!("Open {} in the browser", request.url);
 
-    let access_token = auth::request_microsoft_authorization_token(auth_code)
+    let token = request
+        .wait_for_token(Duration::from_secs(300))
         .await
         .unwrap();
-    let xbox_token = auth::request_xbox_authentication_response(&access_token)
+    let xbox_token = auth::request_xbox_authentication_response(&token.access_token.token)
         .await
         .unwrap();
-    let (xsts_token, uhs) = auth::request_xsts_authorization_response(&xbox_token)
+    let (xsts_token, uhs) = auth::request_xsts_authorization_response(&xbox_token.token)
         .await
         .unwrap();
 
     let mut profile = MinecraftProfile::default();
 
     profile
-        .request_access_token_response(&xsts_token, &uhs)
+        .request_access_token_response(&xsts_token.token, &uhs)
         .await
         .unwrap();
     profile.request_uuid_and_username_response().await.unwrap();
-    profile.save_to_file().await.unwrap();
 
-    let profile = json_processer::read(&CONFIG_DIRECTORY, "profile.json")
+    let mut store = AccountStore::load().await.unwrap();
+    store.add(profile);
+    store.save().await.unwrap();
+
+    let store = json_processer::read(&CONFIG_DIRECTORY, ACCOUNTS_FILE_NAME)
+        .await
+        .unwrap();
+    println!("{}", store);
+}
+
+#[test]
+fn offline_account() {
+    assert_eq!(
+        offline::offline_uuid("Notch"),
+        "b50ad385829d3141a2167e7d7539ba7f"
+    );
+
+    let profile = MinecraftProfile::new_offline("Steve_123").unwrap();
+    assert_eq!(profile.account_type(), AccountType::Offline);
+    assert_eq!(profile.uuid(), offline::offline_uuid("Steve_123"));
+    assert_eq!(profile.user_type(false), "legacy");
+    assert_eq!(profile.user_type(true), "mojang");
+    assert_eq!(profile.xuid(), None);
+
+    assert!(MinecraftProfile::new_offline("ab").is_err());
+    assert!(MinecraftProfile::new_offline("a_very_long_username").is_err());
+    assert!(MinecraftProfile::new_offline("Steve Jobs").is_err());
+    assert!(MinecraftProfile::new_offline("史蒂夫").is_err());
+}
+
+#[tokio::test]
+async fn offline_account_is_not_on_minecraft_services() {
+    let mut profile = MinecraftProfile::new_offline("Steve").unwrap();
+
+    // Every request is refused before anything is sent.
+    let results = [
+        (
+            "request_textures",
+            profile.request_textures().await.map(drop),
+        ),
+        (
+            "upload_skin",
+            profile
+                .upload_skin(Vec::new(), SkinVariant::Slim)
+                .await
+                .map(drop),
+        ),
+        ("reset_skin", profile.reset_skin().await.map(drop)),
+        ("hide_cape", profile.hide_cape().await.map(drop)),
+        (
+            "check_name_availability",
+            profile.check_name_availability("Alex").await.map(drop),
+        ),
+        (
+            "request_name_change_eligibility",
+            profile.request_name_change_eligibility().await.map(drop),
+        ),
+        ("change_name", profile.change_name("Alex").await),
+        (
+            "refresh_player_certificates",
+            profile.refresh_player_certificates().await.map(drop),
+        ),
+        (
+            "request_player_attributes",
+            profile.request_player_attributes().await.map(drop),
+        ),
+    ];
+
+    for (request, result) in results {
+        assert!(
+            matches!(result, Err(AuthError::UnsupportedAccount(_))),
+            "{}",
+            request
+        );
+    }
+    assert_eq!(profile.username(), "Steve");
+    assert_eq!(profile.player_certificates(), None);
+}
+
+#[ignore = "This test case must be manually tested on local machine."]
+#[tokio::test]
+async fn device_code_login_test() {
+    let device_code = auth::request_device_code().await.unwrap();
+    println!("{}", device_code.message);
+
+    let token = auth::poll_device_code_token(&device_code).await.unwrap();
+    let profile = MinecraftProfile::login_with_microsoft_token(token, true)
+        .await
+        .unwrap();
+    println!("{}", profile.username());
+}
+
+#[test]
+fn account_store() {
+    let steve = MinecraftProfile::new_offline("Steve").unwrap();
+    let alex = MinecraftProfile::new_offline("Alex").unwrap();
+
+    let mut store = AccountStore::default();
+    assert!(store.resolve(None).is_err());
+
+    // The first account becomes active.
+    store.add(steve.clone());
+    store.add(alex.clone());
+    assert_eq!(store.list().count(), 2);
+    assert_eq!(store.active().unwrap().username(), "Steve");
+    assert_eq!(store.resolve(None).unwrap(), steve.uuid());
+
+    // An instance may pin another account.
+    assert_eq!(store.resolve(Some(alex.uuid())).unwrap(), alex.uuid());
+    assert!(store.resolve(Some("unknown")).is_err());
+
+    store.select(alex.uuid()).unwrap();
+    assert_eq!(store.active().unwrap().username(), "Alex");
+    assert!(store.select("unknown").is_err());
+
+    // Signing in again replaces the account.
+    assert!(store.add(alex.clone()).is_some());
+    assert_eq!(store.list().count(), 2);
+
+    store.remove(alex.uuid()).unwrap();
+    assert!(store.active().is_none());
+    assert_eq!(store.get(steve.uuid()).unwrap().username(), "Steve");
+}
+
+#[tokio::test]
+async fn encrypted_store() {
+    let dir = std::env::temp_dir().join(format!(
+        "gridcore-account-store-test-{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir).await;
+    fs::create_dir_all(&dir).await.unwrap();
+
+    // Loading an empty store doesn't generate a key.
+    AccountStore::load_from(&dir, StoreKey::KeyFile)
+        .await
+        .unwrap();
+    assert!(fs::metadata(dir.join("accounts.key")).await.is_err());
+
+    // A plaintext profile saved by older versions.
+    let steve = MinecraftProfile::new_offline("Steve").unwrap();
+    fs::write(
+        dir.join("profile.json"),
+        serde_json::to_string(&steve).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut store = AccountStore::load_from(&dir, StoreKey::KeyFile)
+        .await
+        .unwrap();
+    assert_eq!(store.active().unwrap().username(), "Steve");
+    assert!(fs::metadata(dir.join("profile.json")).await.is_err());
+
+    let contents = fs::read_to_string(dir.join(ACCOUNTS_FILE_NAME))
+        .await
+        .unwrap();
+    assert!(contents.contains("Ciphertext"));
+    assert!(!contents.contains(steve.access_token()));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        for file in [ACCOUNTS_FILE_NAME, "accounts.key"] {
+            let metadata = fs::metadata(dir.join(file)).await.unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    let store_again = AccountStore::load_from(&dir, StoreKey::KeyFile)
         .await
         .unwrap();
-    println!("{}", profile);
+    assert_eq!(store_again.active().unwrap().uuid(), steve.uuid());
+
+    // The accounts can't be decrypted without the key, so a new one isn't generated.
+    fs::rename(dir.join("accounts.key"), dir.join("accounts.key.bak"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        AccountStore::load_from(&dir, StoreKey::KeyFile).await,
+        Err(AuthError::EncryptionError(message)) if message.contains("is missing")
+    ));
+    assert!(fs::metadata(dir.join("accounts.key")).await.is_err());
+    fs::rename(dir.join("accounts.key.bak"), dir.join("accounts.key"))
+        .await
+        .unwrap();
+
+    // Protect the store with a passphrase instead.
+    store.set_key(StoreKey::Passphrase("correct horse".to_string()));
+    store.save().await.unwrap();
+
+    assert!(
+        AccountStore::load_from(&dir, StoreKey::KeyFile)
+            .await
+            .is_err()
+    );
+    assert!(
+        AccountStore::load_from(&dir, StoreKey::Passphrase("wrong".to_string()))
+            .await
+            .is_err()
+    );
+    let store = AccountStore::load_from(&dir, StoreKey::Passphrase("correct horse".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(store.list().count(), 1);
+
+    fs::remove_dir_all(&dir).await.unwrap();
+}
+
+#[test]
+fn entitlements() {
+    let jwt = |names: &[&str]| {
+        let entitlements = names
+            .iter()
+            .map(|name| json!({ "name": name }))
+            .collect::<Vec<_>>();
+        let payload = json!({ "entitlements": entitlements, "signerId": "2535405290989141" });
+
+        format!(
+            "eyJhbGciOiJSUzI1NiJ9.{}.signature",
+            URL_SAFE_NO_PAD.encode(payload.to_string())
+        )
+    };
+
+    let purchased = Entitlements::from_json(&json!({
+        "items": [
+            { "name": "product_minecraft", "source": "PURCHASE" },
+            { "name": "game_minecraft", "source": "PURCHASE" },
+            { "name": "product_minecraft_bedrock", "source": "PURCHASE" }
+        ],
+        "signature": jwt(&["product_minecraft", "game_minecraft", "product_minecraft_bedrock"])
+    }));
+    assert_eq!(purchased.java_ownership(), Some(JavaOwnership::Purchase));
+    assert_eq!(
+        purchased
+            .others()
+            .map(|e| e.name.as_str())
+            .collect::<Vec<_>>(),
+        ["product_minecraft_bedrock"]
+    );
+
+    // Bedrock Edition doesn't allow playing Java Edition.
+    let bedrock = Entitlements::from_json(&json!({
+        "items": [
+            { "name": "product_minecraft_bedrock" },
+            { "name": "game_minecraft_bedrock" }
+        ]
+    }));
+    assert_eq!(bedrock.java_ownership(), None);
+    assert_eq!(bedrock.others().count(), 2);
+
+    let game_pass = Entitlements::from_json(&json!({
+        "items": [
+            { "name": "product_game_pass_ultimate" },
+            { "name": "game_minecraft", "source": "GAMEPASS" }
+        ]
+    }));
+    assert_eq!(game_pass.java_ownership(), Some(JavaOwnership::GamePass));
+    assert_eq!(game_pass.others().count(), 0);
+
+    // Items which are not listed in the signature are not trusted.
+    let forged = Entitlements::from_json(&json!({
+        "items": [{ "name": "game_minecraft" }],
+        "signature": jwt(&["product_minecraft_bedrock"])
+    }));
+    assert_eq!(forged.java_ownership(), None);
+
+    assert_eq!(Entitlements::from_json(&json!({})).java_ownership(), None);
+}
+
+#[test]
+fn xbox_errors() {
+    assert_eq!(XboxError::from(2148916233), XboxError::NoXboxAccount);
+    assert_eq!(XboxError::from(2148916235), XboxError::CountryNotSupported);
+    assert_eq!(
+        XboxError::from(2148916236),
+        XboxError::AdultVerificationRequired
+    );
+    assert_eq!(XboxError::from(2148916238), XboxError::ChildAccount);
+    assert_eq!(XboxError::from(1), XboxError::Other(1));
+
+    let error = AuthError::XboxError(XboxError::NoXboxAccount);
+    assert!(error.to_string().contains("Xbox profile"));
+    assert!(
+        AuthError::RateLimited(Some(30))
+            .to_string()
+            .contains("30 seconds")
+    );
+}
+
+#[tokio::test]
+async fn silent_refresh() {
+    // Tokens that haven't expired are not requested again, so no request is sent here.
+    let mut profile: MinecraftProfile = serde_json::from_value(json!({
+        "AccessToken": "minecraft",
+        "Uuid": "069a79f444e94726a5befca90e38aaf5",
+        "Username": "Notch",
+        "MicrosoftCredentials": {
+            "MicrosoftToken": {
+                "AccessToken": { "Token": "microsoft", "ExpiresAt": u64::MAX / 2 },
+                "RefreshToken": "refresh"
+            },
+            "XboxToken": { "Token": "xbox", "ExpiresAt": u64::MAX / 2 },
+            "XstsToken": { "Token": "xsts", "ExpiresAt": u64::MAX / 2 },
+            "Uhs": "uhs",
+            "MinecraftExpiresAt": u64::MAX / 2
+        },
+        "Xuid": "2535405290989179"
+    }))
+    .unwrap();
+
+    assert_eq!(profile.account_type(), AccountType::Microsoft);
+    assert_eq!(profile.xuid(), Some("2535405290989179"));
+    assert!(!profile.refresh().await.unwrap());
+    assert_eq!(profile.access_token(), "minecraft");
+    assert_eq!(
+        profile
+            .microsoft_credentials()
+            .unwrap()
+            .microsoft_token
+            .refresh_token,
+        "refresh"
+    );
+
+    // None of the tokens are printed in logs.
+    let printed = format!("{:?}", profile);
+    assert!(printed.contains("2535405290989179"));
+    for token in ["minecraft", "microsoft", "refresh", "xbox", "xsts"] {
+        assert!(!printed.contains(&format!("\"{}\"", token)));
+    }
+
+    // Profiles saved by older versions have nothing to refresh with.
+    let mut profile: MinecraftProfile = serde_json::from_value(json!({
+        "AccessToken": "minecraft",
+        "Uuid": "069a79f444e94726a5befca90e38aaf5",
+        "Username": "Notch"
+    }))
+    .unwrap();
+    assert!(!profile.refresh().await.unwrap());
+}
+
+/// Stands in for every service in the chain of Microsoft login.
+fn microsoft_stand_in(request: Request) -> Response {
+    let json = |body: Value| Response::new(200, body.to_string());
+
+    match request.path.as_str() {
+        "/consumers/oauth2/v2.0/token" if request.body.contains("refresh_token=revoked") => {
+            Response::new(
+                400,
+                r#"{"error":"invalid_grant","error_description":"The refresh token was revoked."}"#,
+            )
+        }
+        "/consumers/oauth2/v2.0/token" if request.body.contains("refresh_token=refresh") => {
+            json(json!({
+                "access_token": "microsoft-2",
+                "refresh_token": "refresh-2",
+                "expires_in": 3600
+            }))
+        }
+        "/user/authenticate" if request.body.contains("d=microsoft-2") => json(json!({
+            "Token": "xbox-2",
+            "NotAfter": "2999-01-01T00:00:00.0000000Z"
+        })),
+        "/xsts/authorize" if request.body.contains("xbox-2") => json(json!({
+            "Token": "xsts-2",
+            "NotAfter": "2999-01-01T00:00:00.0000000Z",
+            "DisplayClaims": { "xui": [{ "uhs": "uhs-2" }] }
+        })),
+        // An access token which isn't a JWT.
+        "/authentication/login_with_xbox" if request.body.contains("XBL3.0 x=uhs-3;xsts-3") => {
+            json(json!({ "access_token": "opaque", "expires_in": 86400 }))
+        }
+        "/authentication/login_with_xbox" if request.body.contains("XBL3.0 x=uhs-2;xsts-2") => {
+            let payload = json!({ "xuid": "2535405290989179", "exp": 4102444800u64 });
+
+            json(json!({
+                "access_token": format!(
+                    "eyJhbGciOiJIUzI1NiJ9.{}.signature",
+                    URL_SAFE_NO_PAD.encode(payload.to_string())
+                ),
+                "expires_in": 86400
+            }))
+        }
+        _ => Response::new(400, ""),
+    }
+}
+
+/// How many times the token endpoint has been polled with the device code `pending`.
+static DEVICE_CODE_POLLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Stands in for the endpoints of signing in with a device code.
+fn device_code_stand_in(request: Request) -> Response {
+    let error = |error: &str| {
+        Response::new(
+            400,
+            json!({ "error": error, "error_description": error }).to_string(),
+        )
+    };
+
+    match request.path.as_str() {
+        "/consumers/oauth2/v2.0/devicecode" => Response::new(
+            200,
+            json!({
+                "device_code": "pending",
+                "user_code": "ABCD1234",
+                "verification_uri": "https://www.microsoft.com/link",
+                "expires_in": 900,
+                "interval": 0,
+                "message": "To sign in, use a web browser to open the page https://www.microsoft.com/link and enter the code ABCD1234 to authenticate."
+            })
+            .to_string(),
+        ),
+        "/consumers/oauth2/v2.0/token" if request.body.contains("device_code=pending") => {
+            match DEVICE_CODE_POLLS.fetch_add(1, Ordering::SeqCst) {
+                0 => error("authorization_pending"),
+                1 => error("slow_down"),
+                _ => Response::new(
+                    200,
+                    json!({
+                        "access_token": "microsoft-device",
+                        "refresh_token": "refresh-device",
+                        "expires_in": 3600
+                    })
+                    .to_string(),
+                ),
+            }
+        }
+        "/consumers/oauth2/v2.0/token" if request.body.contains("device_code=expired") => {
+            error("expired_token")
+        }
+        _ => Response::new(404, ""),
+    }
+}
+
+/// A profile whose tokens have all expired, except the XSTS token which expires at `xsts_expires_at`.
+fn microsoft_profile(refresh_token: &str, xsts_expires_at: u64) -> MinecraftProfile {
+    serde_json::from_value(json!({
+        "AccessToken": "minecraft",
+        "Uuid": "069a79f444e94726a5befca90e38aaf5",
+        "Username": "Notch",
+        "MicrosoftCredentials": {
+            "MicrosoftToken": {
+                "AccessToken": { "Token": "microsoft", "ExpiresAt": 0 },
+                "RefreshToken": refresh_token
+            },
+            "XboxToken": { "Token": "xbox", "ExpiresAt": 0 },
+            "XstsToken": { "Token": "xsts-2", "ExpiresAt": xsts_expires_at },
+            "Uhs": "uhs-2",
+            "MinecraftExpiresAt": 0
+        }
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn refresh_chain() {
+    let homepage = common::serve(microsoft_stand_in).await;
+    let endpoints = MicrosoftEndpoints::with_root(&homepage);
+    assert_eq!(
+        endpoints.xsts_authorize,
+        format!("{}/xsts/authorize", homepage)
+    );
+
+    // Every token has expired, so the whole chain is requested again from the refresh token.
+    let mut profile = microsoft_profile("refresh", 0);
+    assert!(profile.refresh_with(&endpoints).await.unwrap());
+    assert_eq!(profile.xuid(), Some("2535405290989179"));
+
+    let credentials = profile.microsoft_credentials().unwrap();
+    assert_eq!(credentials.microsoft_token.refresh_token, "refresh-2");
+    assert_eq!(credentials.xbox_token.token, "xbox-2");
+    assert_eq!(credentials.xsts_token.token, "xsts-2");
+    assert_eq!(credentials.uhs, "uhs-2");
+    assert!(credentials.minecraft_expires_at > 0);
+
+    // Nothing has expired any more.
+    assert!(!profile.refresh_with(&endpoints).await.unwrap());
+
+    // The XSTS token is still valid, so the revoked refresh token is never used.
+    let mut profile = microsoft_profile("revoked", u64::MAX / 2);
+    assert!(profile.refresh_with(&endpoints).await.unwrap());
+    assert_eq!(profile.xuid(), Some("2535405290989179"));
+
+    // The player has to sign in again, and the saved tokens are kept as they were.
+    let mut profile = microsoft_profile("revoked", 0);
+    assert!(matches!(
+        profile.refresh_with(&endpoints).await,
+        Err(AuthError::LoginRequired(_))
+    ));
+    assert_eq!(profile.access_token(), "minecraft");
+    assert_eq!(
+        profile
+            .microsoft_credentials()
+            .unwrap()
+            .microsoft_token
+            .refresh_token,
+        "revoked"
+    );
+}
+
+#[tokio::test]
+async fn device_code_polling() {
+    let homepage = common::serve(device_code_stand_in).await;
+    let endpoints = MicrosoftEndpoints::with_root(&homepage);
+
+    let device_code = auth::request_device_code_with(&endpoints).await.unwrap();
+    assert_eq!(device_code.user_code, "ABCD1234");
+    assert_eq!(device_code.interval, 0);
+
+    // The player signs in after a pending poll, and the server asks to slow down once,
+    // which adds 5 seconds to the interval.
+    let started = Instant::now();
+    let token = auth::poll_device_code_token_with(&device_code, &endpoints)
+        .await
+        .unwrap();
+    assert_eq!(token.access_token.token, "microsoft-device");
+    assert_eq!(token.refresh_token, "refresh-device");
+    assert_eq!(DEVICE_CODE_POLLS.load(Ordering::SeqCst), 3);
+    assert!(started.elapsed() >= Duration::from_secs(5));
+
+    let expired = auth::DeviceCode {
+        device_code: "expired".to_string(),
+        ..device_code
+    };
+    assert!(matches!(
+        auth::poll_device_code_token_with(&expired, &endpoints).await,
+        Err(AuthError::OAuthError(message)) if message.contains("expired")
+    ));
+}
+
+#[tokio::test]
+async fn xuid_from_access_token() {
+    let homepage = common::serve(microsoft_stand_in).await;
+    let endpoints = MicrosoftEndpoints::with_root(&homepage);
+
+    // The XUID is the `xuid` claim of the Minecraft access token, which is a JWT.
+    let mut profile = microsoft_profile("refresh", u64::MAX / 2);
+    profile.refresh_with(&endpoints).await.unwrap();
+    assert_eq!(profile.xuid(), Some("2535405290989179"));
+
+    // The XUID isn't known if the token can't be decoded, rather than failing to sign in.
+    let mut profile: MinecraftProfile = serde_json::from_value(json!({
+        "AccessToken": "minecraft",
+        "Uuid": "069a79f444e94726a5befca90e38aaf5",
+        "Username": "Notch",
+        "MicrosoftCredentials": {
+            "MicrosoftToken": {
+                "AccessToken": { "Token": "microsoft", "ExpiresAt": u64::MAX / 2 },
+                "RefreshToken": "refresh"
+            },
+            "XboxToken": { "Token": "xbox", "ExpiresAt": u64::MAX / 2 },
+            "XstsToken": { "Token": "xsts-3", "ExpiresAt": u64::MAX / 2 },
+            "Uhs": "uhs-3",
+            "MinecraftExpiresAt": 0
+        },
+        "Xuid": "2535405290989179"
+    }))
+    .unwrap();
+    assert!(profile.refresh_with(&endpoints).await.unwrap());
+    assert_eq!(profile.access_token(), "opaque");
+    assert_eq!(profile.xuid(), None);
+}
+
+#[tokio::test]
+async fn loopback_redirect() {
+    let request = AuthorizationRequest::start().await.unwrap();
+    assert_eq!(query(&request.url, "code_challenge_method"), "S256");
+    let redirect_uri = query(&request.url, "redirect_uri");
+    let state = query(&request.url, "state");
+
+    // Browsers open connections in advance, which may never send anything.
+    let idle = TcpStream::connect(redirect_uri.trim_start_matches("http://"))
+        .await
+        .unwrap();
+
+    let browser = tokio::spawn(async move {
+        // Requests without the state are ignored.
+        let favicon = reqwest::get(format!("{}/favicon.ico", redirect_uri))
+            .await
+            .unwrap();
+        assert_eq!(favicon.status(), 404);
+
+        reqwest::get(format!("{}/?code=M.C123&state={}", redirect_uri, state))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+    });
+
+    let code = request
+        .wait_for_code(Duration::from_secs(10))
+        .await
+        .unwrap();
+    assert_eq!(code, "M.C123");
+    assert!(browser.await.unwrap().contains("Signed in successfully"));
+    drop(idle);
+
+    let request = AuthorizationRequest::start().await.unwrap();
+    let redirect_uri = query(&request.url, "redirect_uri");
+    let state = query(&request.url, "state");
+
+    tokio::spawn(async move {
+        reqwest::get(format!(
+            "{}/?error=access_denied&error_description=Declined&state={}",
+            redirect_uri, state
+        ))
+        .await
+        .unwrap();
+    });
+    assert!(
+        request
+            .wait_for_code(Duration::from_secs(10))
+            .await
+            .is_err()
+    );
+
+    // The player never finishes signing in.
+    let request = AuthorizationRequest::start().await.unwrap();
+    assert!(
+        request
+            .wait_for_code(Duration::from_millis(100))
+            .await
+            .is_err()
+    );
+}
+
+fn query(url: &str, key: &str) -> String {
+    Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+        .unwrap()
 }
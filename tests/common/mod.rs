@@ -0,0 +1,109 @@
+//! A stand-in HTTP server for tests which must not touch real services.
+
+// Each test crate only uses part of the helpers.
+#![allow(dead_code)]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Starts a server on a random local port, and returns its base URL, e.g. `http://127.0.0.1:12345`.
+///
+/// Each request is answered by `handler`.
+pub async fn serve(handler: fn(Request) -> Response) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                break;
+            };
+
+            tokio::spawn(async move {
+                let mut buffer = Vec::new();
+                let mut chunk = [0; 4096];
+
+                // Reads the head, and then the body according to `Content-Length`.
+                let (head, body) = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    buffer.extend_from_slice(&chunk[..n]);
+
+                    if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&buffer[..pos]).into_owned();
+                        let length = head
+                            .lines()
+                            .find_map(|line| {
+                                let (name, value) = line.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+
+                        while buffer.len() < pos + 4 + length {
+                            let n = stream.read(&mut chunk).await.unwrap();
+                            buffer.extend_from_slice(&chunk[..n]);
+                        }
+
+                        let body = String::from_utf8_lossy(&buffer[pos + 4..pos + 4 + length])
+                            .into_owned();
+                        break (head, body);
+                    }
+                };
+
+                let mut request_line = head.lines().next().unwrap().split(' ');
+                let request = Request {
+                    method: request_line.next().unwrap().to_string(),
+                    path: request_line.next().unwrap().to_string(),
+                    body,
+                };
+
+                let response = handler(request);
+                let mut output = format!(
+                    "HTTP/1.1 {} Stand-in\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in response.headers {
+                    output.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                output.push_str("\r\n");
+
+                stream.write_all(output.as_bytes()).await.unwrap();
+                stream.write_all(&response.body).await.unwrap();
+                stream.shutdown().await.unwrap();
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
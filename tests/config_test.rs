@@ -0,0 +1,34 @@
+use gridcore::config::Config;
+
+use std::env;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+#[tokio::test]
+async fn client_id() {
+    let directory = env::temp_dir().join(format!("gridcore-config-{}", std::process::id()));
+    let _ = tokio::fs::remove_dir_all(&directory).await;
+
+    let mut config = Config::load_from(&directory).await.unwrap();
+    let client_id = config.ensure_client_id().await.unwrap();
+    assert_eq!(config.ensure_client_id().await.unwrap(), client_id);
+
+    // It's saved, so the next launch passes the same one.
+    let mut config = Config::load_from(&directory).await.unwrap();
+    assert_eq!(config.client_id.as_deref(), Some(client_id.as_str()));
+    assert_eq!(config.ensure_client_id().await.unwrap(), client_id);
+
+    // It's a version 4 UUID of the IETF variant, e.g. `xxxxxxxx-xxxx-4xxx-8xxx-xxxxxxxxxxxx`.
+    let uuid = String::from_utf8(STANDARD.decode(&client_id).unwrap()).unwrap();
+    let groups = uuid.split('-').collect::<Vec<_>>();
+    assert_eq!(
+        groups.iter().map(|group| group.len()).collect::<Vec<_>>(),
+        [8, 4, 4, 4, 12]
+    );
+    assert!(uuid.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+    assert!(groups[2].starts_with('4'));
+    assert!(groups[3].starts_with(['8', '9', 'a', 'b']));
+
+    tokio::fs::remove_dir_all(&directory).await.unwrap();
+}
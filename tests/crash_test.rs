@@ -0,0 +1,124 @@
+use gridcore::managers::game::crash::{self, KnownCause};
+
+use std::time::SystemTime;
+
+use tokio::fs;
+
+const FABRIC_CRASH_REPORT: &str = r#"---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2025-06-01 12:00:00
+Description: Initializing game
+
+java.lang.RuntimeException: Mixin transformation of net.minecraft.class_310 failed
+	at net.fabricmc.loader.impl.launch.knot.KnotClassDelegate.getPostMixinClassByteArray(KnotClassDelegate.java:427) ~[fabric-loader-0.16.14.jar:?]
+Caused by: org.spongepowered.asm.mixin.transformer.throwables.MixinTransformerError: An unexpected critical error was encountered
+	at org.spongepowered.asm.mixin.transformer.MixinProcessor.applyMixins(MixinProcessor.java:392) ~[sponge-mixin-0.15.5.jar:?]
+Caused by: org.spongepowered.asm.mixin.injection.throwables.InjectionError: Critical injection failure: Mixin [badmod.mixins.json:MinecraftClientMixin] from mod badmod failed injection check
+	at org.spongepowered.asm.mixin.injection.struct.InjectionInfo.postInject(InjectionInfo.java:468) ~[sponge-mixin-0.15.5.jar:?]
+"#;
+
+#[test]
+fn mixin_crash() {
+    let diagnosis = crash::diagnose(Some(FABRIC_CRASH_REPORT), None, "");
+
+    assert_eq!(diagnosis.exceptions.len(), 3);
+    assert!(diagnosis.exceptions[0].starts_with("java.lang.RuntimeException: Mixin"));
+    assert_eq!(diagnosis.suspected_mod.as_deref(), Some("badmod"));
+    assert_eq!(
+        diagnosis.causes,
+        [KnownCause::MixinApplyFailure(Some("badmod".to_string()))]
+    );
+    assert!(diagnosis.hint().contains("badmod"));
+}
+
+#[test]
+fn known_causes() {
+    let log = "[12:00:00] [main/ERROR]: Exception in thread \"main\" \
+java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been compiled by \
+a more recent version of the Java Runtime (class file version 65.0)";
+    let diagnosis = crash::diagnose(None, None, log);
+    assert_eq!(diagnosis.causes, [KnownCause::WrongJavaVersion(Some(21))]);
+
+    // Class file versions this old never existed, so no Java version is suggested.
+    let log = "java.lang.UnsupportedClassVersionError: Main (class file version 40.0)";
+    let diagnosis = crash::diagnose(None, None, log);
+    assert_eq!(diagnosis.causes, [KnownCause::WrongJavaVersion(None)]);
+
+    let log = "[12:00:00] [Render thread/ERROR]: GLFW error 65542: WGL: The driver does not appear to support OpenGL";
+    let diagnosis = crash::diagnose(None, None, log);
+    assert_eq!(diagnosis.causes, [KnownCause::MissingOpenGl]);
+
+    let hs_err =
+        "#\n# There is insufficient memory for the Java Runtime Environment to continue.\n";
+    let diagnosis = crash::diagnose(None, Some(hs_err), "");
+    assert_eq!(diagnosis.causes, [KnownCause::OutOfMemory]);
+
+    let hs_err = "#\n# Problematic frame:\n# C  [atio6axx.dll+0x1a2b3c]\n#\n";
+    let diagnosis = crash::diagnose(None, Some(hs_err), "");
+    assert_eq!(
+        diagnosis.causes,
+        [KnownCause::JvmCrash(
+            "C  [atio6axx.dll+0x1a2b3c]".to_string()
+        )]
+    );
+}
+
+#[test]
+fn suspected_mod_from_frames() {
+    let report = "java.lang.NullPointerException: Cannot invoke \"Object.toString()\"
+	at TRANSFORMER/minecraft@1.20.1/net.minecraft.client.Minecraft.run(Minecraft.java:100)
+	at TRANSFORMER/coolmod@1.0.0/com.example.coolmod.Handler.tick(Handler.java:42)
+";
+    let diagnosis = crash::diagnose(Some(report), None, "");
+
+    assert_eq!(diagnosis.suspected_mod.as_deref(), Some("coolmod"));
+    assert!(diagnosis.causes.is_empty());
+    assert!(diagnosis.hint().contains("NullPointerException"));
+    assert!(diagnosis.hint().contains("coolmod"));
+}
+
+#[tokio::test]
+async fn analyze_game_dir() {
+    let game_dir = std::env::temp_dir().join(format!("gridcore-crash-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&game_dir).await;
+
+    fs::create_dir_all(game_dir.join("crash-reports"))
+        .await
+        .unwrap();
+    fs::create_dir_all(game_dir.join("logs")).await.unwrap();
+    fs::write(
+        game_dir.join("crash-reports/crash-2025-06-01_12.00.00-client.txt"),
+        FABRIC_CRASH_REPORT,
+    )
+    .await
+    .unwrap();
+    fs::write(
+        game_dir.join("hs_err_pid1234.log"),
+        "# Problematic frame:\n# V  [libjvm.so+0x123456]\n",
+    )
+    .await
+    .unwrap();
+    fs::write(
+        game_dir.join("logs/latest.log"),
+        "[12:00:00] [main/INFO]: Hello\n",
+    )
+    .await
+    .unwrap();
+
+    let diagnosis = crash::analyze_crash(&game_dir, SystemTime::UNIX_EPOCH)
+        .await
+        .unwrap();
+    assert!(diagnosis.crash_report.is_some());
+    assert!(diagnosis.jvm_crash_log.is_some());
+    assert_eq!(diagnosis.causes.len(), 2);
+
+    // Files left before the launch are ignored.
+    let diagnosis = crash::analyze_crash(&game_dir, SystemTime::now())
+        .await
+        .unwrap();
+    assert!(diagnosis.crash_report.is_none());
+    assert!(diagnosis.causes.is_empty());
+
+    fs::remove_dir_all(&game_dir).await.unwrap();
+}
@@ -1 +1,434 @@
+use gridcore::config::InstanceConfig;
+use gridcore::constants::MINECRAFT_ROOT;
+use gridcore::managers::game::launch::{
+    self, LaunchArguments, LaunchEvent, LaunchOptions, QuickPlay, Resolution,
+};
+use gridcore::managers::version;
 
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+
+use serde_json::json;
+use tokio::sync::mpsc;
+
+fn command(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn feature_args() {
+    let data = json!({
+        "arguments": {
+            "game": [
+                "--username",
+                "${auth_player_name}",
+                {
+                    "rules": [{ "action": "allow", "features": { "is_demo_user": true } }],
+                    "value": "--demo"
+                },
+                {
+                    "rules": [{ "action": "allow", "features": { "has_custom_resolution": true } }],
+                    "value": ["--width", "${resolution_width}", "--height", "${resolution_height}"]
+                },
+                {
+                    "rules": [{ "action": "allow", "features": { "is_quick_play_multiplayer": true } }],
+                    "value": ["--quickPlayMultiplayer", "${quickPlayMultiplayer}"]
+                }
+            ],
+            "jvm": []
+        }
+    });
+
+    let args = LaunchArguments::from_json(&data, &LaunchOptions::default());
+    assert_eq!(args.game, ["--username", "${auth_player_name}"]);
+
+    let options = LaunchOptions {
+        resolution: Some(Resolution(1280, 720)),
+        fullscreen: true,
+        demo: true,
+        quick_play: Some(QuickPlay::Multiplayer("mc.example.com".to_string())),
+        ..Default::default()
+    };
+    let args = LaunchArguments::from_json(&data, &options);
+    assert_eq!(
+        args.game,
+        [
+            "--username",
+            "${auth_player_name}",
+            "--demo",
+            "--width",
+            "${resolution_width}",
+            "--height",
+            "${resolution_height}",
+            "--quickPlayMultiplayer",
+            "${quickPlayMultiplayer}",
+            "--fullscreen"
+        ]
+    );
+}
+
+#[test]
+fn legacy_args() {
+    let data = json!({
+        "minecraftArguments": "--username ${auth_player_name}"
+    });
+
+    let options = LaunchOptions {
+        resolution: Some(Resolution(854, 480)),
+        quick_play: Some(QuickPlay::Multiplayer("mc.example.com:25566".to_string())),
+        ..Default::default()
+    };
+    let args = LaunchArguments::from_json(&data, &options);
+    assert_eq!(
+        args.game,
+        [
+            "--username",
+            "${auth_player_name}",
+            "--width",
+            "854",
+            "--height",
+            "480",
+            "--server",
+            "mc.example.com",
+            "--port",
+            "25566"
+        ]
+    );
+    // Old versions don't declare JVM arguments, so the default ones are used.
+    assert!(
+        args.jvm
+            .contains(&"-Djava.library.path=${natives_directory}".to_string())
+    );
+    assert!(args.jvm.ends_with(&command(&["-cp", "${classpath}"])));
+}
+
+#[test]
+fn legacy_server_address() {
+    let data = json!({ "minecraftArguments": "" });
+
+    for (server, host, port) in [
+        ("mc.example.com", "mc.example.com", "25565"),
+        ("127.0.0.1:25566", "127.0.0.1", "25566"),
+        ("[2001:db8::1]:25566", "2001:db8::1", "25566"),
+        ("[::1]", "::1", "25565"),
+        // A bare IPv6 address can't be followed by a port.
+        ("2001:db8::1", "2001:db8::1", "25565"),
+    ] {
+        let options = LaunchOptions {
+            quick_play: Some(QuickPlay::Multiplayer(server.to_string())),
+            ..Default::default()
+        };
+        let args = LaunchArguments::from_json(&data, &options);
+        assert!(
+            args.game
+                .ends_with(&command(&["--server", host, "--port", port])),
+            "{}",
+            server
+        );
+    }
+}
+
+#[test]
+fn version_classpath() {
+    let data = json!({
+        "id": "fabric-loader-0.16.14-1.21.5",
+        "jar": "1.21.5",
+        "libraries": [
+            { "name": "net.fabricmc:fabric-loader:0.16.14", "url": "https://maven.fabricmc.net/" },
+            {
+                "name": "org.ow2.asm:asm:9.8",
+                "downloads": { "artifact": { "path": "org/ow2/asm/asm/9.8/asm-9.8.jar" } }
+            },
+            { "name": "org.ow2.asm:asm:9.8", "url": "https://maven.fabricmc.net/" },
+            {
+                "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+                "natives": { "linux": "natives-linux" },
+                "downloads": {
+                    "classifiers": {
+                        "natives-linux": {
+                            "path": "org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-natives-linux.jar"
+                        }
+                    }
+                }
+            },
+            { "name": "com.example:disallowed:1.0", "rules": [{ "action": "disallow" }] }
+        ]
+    });
+
+    // Duplicates, libraries only packing natives and disallowed libraries are left out.
+    assert_eq!(
+        launch::classpath(&data),
+        [
+            format!(
+                "{}/libraries/net/fabricmc/fabric-loader/0.16.14/fabric-loader-0.16.14.jar",
+                MINECRAFT_ROOT
+            ),
+            format!(
+                "{}/libraries/org/ow2/asm/asm/9.8/asm-9.8.jar",
+                MINECRAFT_ROOT
+            ),
+            format!(
+                "{}/versions/fabric-loader-0.16.14-1.21.5/fabric-loader-0.16.14-1.21.5.jar",
+                MINECRAFT_ROOT
+            )
+        ]
+    );
+}
+
+#[test]
+fn natives_classifier() {
+    let lwjgl2 = json!({
+        "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4-nightly-20150209",
+        "natives": { "linux": "natives-linux", "osx": "natives-osx", "windows": "natives-windows" },
+        "downloads": {
+            "classifiers": {
+                "natives-linux": { "path": "lwjgl-platform-natives-linux.jar" },
+                "natives-osx": { "path": "lwjgl-platform-natives-osx.jar" },
+                "natives-windows": { "path": "lwjgl-platform-natives-windows.jar" }
+            }
+        }
+    });
+    let lwjgl3 = json!({
+        "name": "org.lwjgl:lwjgl:3.2.2",
+        "natives": { "linux": "natives-linux", "osx": "natives-macos", "windows": "natives-windows" },
+        "downloads": {
+            "classifiers": {
+                "natives-linux": { "path": "lwjgl-natives-linux.jar" },
+                "natives-macos": { "path": "lwjgl-natives-macos.jar" },
+                "natives-windows": { "path": "lwjgl-natives-windows.jar" }
+            }
+        }
+    });
+    let twitch = json!({
+        "name": "tv.twitch:twitch-platform:6.5",
+        "natives": { "windows": "natives-windows-${arch}" },
+        "downloads": {
+            "classifiers": {
+                "natives-windows-32": { "path": "twitch-platform-natives-windows-32.jar" },
+                "natives-windows-64": { "path": "twitch-platform-natives-windows-64.jar" }
+            }
+        }
+    });
+
+    // The classifier of macOS is named differently by LWJGL 2 and LWJGL 3.
+    assert_eq!(
+        launch::natives_path(&lwjgl2, "osx"),
+        Some("lwjgl-platform-natives-osx.jar")
+    );
+    assert_eq!(
+        launch::natives_path(&lwjgl3, "osx"),
+        Some("lwjgl-natives-macos.jar")
+    );
+    assert_eq!(
+        launch::natives_path(&lwjgl3, "linux"),
+        Some("lwjgl-natives-linux.jar")
+    );
+    assert_eq!(
+        launch::natives_path(&twitch, "windows"),
+        Some(if cfg!(target_pointer_width = "64") {
+            "twitch-platform-natives-windows-64.jar"
+        } else {
+            "twitch-platform-natives-windows-32.jar"
+        })
+    );
+    // Libraries without natives for the platform are skipped.
+    assert_eq!(launch::natives_path(&twitch, "linux"), None);
+    assert_eq!(
+        launch::natives_path(&json!({ "name": "org.ow2.asm:asm:9.8" }), "linux"),
+        None
+    );
+}
+
+#[test]
+fn forge_jvm_args() {
+    let parent = json!({
+        "id": "1.20.1",
+        "arguments": { "game": [], "jvm": ["-cp", "${classpath}"] },
+        "libraries": []
+    });
+    let child = json!({
+        "id": "1.20.1-forge-47.4.0",
+        "inheritsFrom": "1.20.1",
+        "arguments": {
+            "jvm": [
+                "-DignoreList=bootstraplauncher,securejarhandler,forge-,${version_name}.jar",
+                "-DlibraryDirectory=${library_directory}"
+            ]
+        }
+    });
+    let data = version::merge_version_json(parent, &child);
+
+    let mut args = LaunchArguments::from_json(&data, &LaunchOptions::default());
+    args.replace_jvm_placeholders(&data, "1.20.1-forge-47.4.0")
+        .unwrap();
+
+    // Forge skips the game jar on the classpath by its file name.
+    let jar = format!(
+        "{}/versions/1.20.1-forge-47.4.0/1.20.1-forge-47.4.0.jar",
+        MINECRAFT_ROOT
+    );
+    assert_eq!(args.jvm[1], jar);
+    assert_eq!(
+        args.jvm[2],
+        "-DignoreList=bootstraplauncher,securejarhandler,forge-,1.20.1-forge-47.4.0.jar"
+    );
+    assert_eq!(launch::classpath(&data).last(), Some(&jar));
+}
+
+#[test]
+fn neoforge_jvm_args() {
+    let parent = json!({
+        "id": "1.21.1",
+        "arguments": { "game": [], "jvm": ["-cp", "${classpath}"] },
+        "libraries": []
+    });
+    let child = json!({
+        "id": "neoforge-21.1.172",
+        "inheritsFrom": "1.21.1",
+        "arguments": {
+            "jvm": [
+                "-DignoreList=client-extra,${version_name}.jar",
+                "-p",
+                "${library_directory}/cpw/mods/bootstraplauncher/2.0.2/bootstraplauncher-2.0.2.jar"
+            ]
+        }
+    });
+    let data = version::merge_version_json(parent, &child);
+
+    let mut args = LaunchArguments::from_json(&data, &LaunchOptions::default());
+    args.replace_jvm_placeholders(&data, "neoforge-21.1.172")
+        .unwrap();
+
+    let jar = format!(
+        "{}/versions/neoforge-21.1.172/neoforge-21.1.172.jar",
+        MINECRAFT_ROOT
+    );
+    assert_eq!(
+        args.jvm,
+        [
+            "-cp".to_string(),
+            jar.clone(),
+            "-DignoreList=client-extra,neoforge-21.1.172.jar".to_string(),
+            "-p".to_string(),
+            format!(
+                "{}/libraries/cpw/mods/bootstraplauncher/2.0.2/bootstraplauncher-2.0.2.jar",
+                MINECRAFT_ROOT
+            )
+        ]
+    );
+    assert_eq!(launch::classpath(&data).last(), Some(&jar));
+}
+
+#[test]
+fn wrapped_command() {
+    let instance = InstanceConfig {
+        wrappers: vec![
+            vec!["gamemoderun".to_string()],
+            vec!["nice".to_string(), "-n".to_string(), "10".to_string()],
+        ],
+        env: BTreeMap::from([("__GL_THREADED_OPTIMIZATIONS".to_string(), "1".to_string())]),
+        ..Default::default()
+    };
+
+    let command = launch::game_command(vec!["-Xmx2G".to_string()], &instance);
+    assert_eq!(command.get_program(), "gamemoderun");
+    assert_eq!(
+        command.get_args().collect::<Vec<_>>(),
+        ["nice", "-n", "10", "java", "-Xmx2G"]
+    );
+    assert_eq!(
+        command.get_envs().collect::<Vec<_>>(),
+        [(
+            OsStr::new("__GL_THREADED_OPTIMIZATIONS"),
+            Some(OsStr::new("1"))
+        )]
+    );
+
+    let command = launch::game_command(Vec::new(), &InstanceConfig::default());
+    assert_eq!(command.get_program(), "java");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn hooks() {
+    // The wrapper stands in for the game, so that Java is not required.
+    let instance = InstanceConfig {
+        wrappers: vec![command(&["sh", "-c", "echo game; exit 3", "sh"])],
+        pre_launch: vec![command(&["echo", "pre"])],
+        post_exit: vec![command(&[
+            "sh",
+            "-c",
+            "echo ${exit_code} $GRIDCORE_EXIT_CODE $GRIDCORE_LOG_PATH",
+        ])],
+        ..Default::default()
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let status = launch::launch_game(Vec::new(), &instance, tx)
+        .await
+        .unwrap();
+    assert_eq!(status.code(), Some(3));
+
+    let mut lines = Vec::new();
+    while let Some(event) = rx.recv().await {
+        match event {
+            LaunchEvent::HookOutput { line, .. } => lines.push(line),
+            LaunchEvent::GameLog(record) => lines.push(record.message),
+            _ => {}
+        }
+    }
+    assert_eq!(lines, ["pre", "game", "3 3 ./.minecraft/logs/latest.log"]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn failed_pre_launch_hook() {
+    let instance = InstanceConfig {
+        wrappers: vec![command(&["sh", "-c", "echo game", "sh"])],
+        pre_launch: vec![command(&["false"])],
+        ..Default::default()
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    assert!(
+        launch::launch_game(Vec::new(), &instance, tx)
+            .await
+            .is_err()
+    );
+
+    while let Some(event) = rx.recv().await {
+        assert!(!matches!(event, LaunchEvent::GameLog(_)));
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn failed_post_exit_hook() {
+    let instance = InstanceConfig {
+        wrappers: vec![command(&["sh", "-c", "echo game", "sh"])],
+        post_exit: vec![
+            command(&["gridcore-missing-hook"]),
+            command(&["echo", "post"]),
+        ],
+        ..Default::default()
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let status = launch::launch_game(Vec::new(), &instance, tx)
+        .await
+        .unwrap();
+    assert!(status.success());
+
+    let mut failed = Vec::new();
+    let mut lines = Vec::new();
+    while let Some(event) = rx.recv().await {
+        match event {
+            LaunchEvent::HookFailed { hook, .. } => failed.push(hook),
+            LaunchEvent::HookOutput { line, .. } => lines.push(line),
+            _ => {}
+        }
+    }
+    assert_eq!(failed, ["gridcore-missing-hook"]);
+    // The remaining hooks are still run.
+    assert_eq!(lines, ["post"]);
+}
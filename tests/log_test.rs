@@ -0,0 +1,71 @@
+use gridcore::managers::game::log::{LogLevel, LogParser, LogRecord};
+
+#[test]
+fn xml_events() {
+    let output = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: Steve]]></log4j:Message>
+</log4j:Event>
+
+<log4j:Event logger="net.minecraft.server.Main" timestamp="1700000000001" level="ERROR" thread="Server &quot;main&quot;">
+  <log4j:Message><![CDATA[Failed to load <world>]]]]><![CDATA[>]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.lang.IllegalStateException: Broken
+	at net.minecraft.server.Main.main(Main.java:1)
+]]></log4j:Throwable>
+</log4j:Event>
+"#;
+
+    let mut parser = LogParser::new();
+    let records = output
+        .lines()
+        .filter_map(|line| parser.feed(line))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        records[0],
+        LogRecord {
+            timestamp: Some(1700000000000),
+            level: LogLevel::Info,
+            thread: "Render thread".to_string(),
+            logger: "net.minecraft.client.Minecraft".to_string(),
+            message: "Setting user: Steve".to_string(),
+            throwable: None,
+        }
+    );
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].level, LogLevel::Error);
+    assert_eq!(records[1].thread, "Server \"main\"");
+    assert_eq!(records[1].message, "Failed to load <world>]]>");
+    assert!(
+        records[1]
+            .throwable
+            .as_deref()
+            .unwrap()
+            .starts_with("java.lang.IllegalStateException: Broken\n\tat")
+    );
+    assert!(parser.finish().is_none());
+}
+
+#[test]
+fn plain_text() {
+    let mut parser = LogParser::new();
+
+    let record = parser
+        .feed("[12:00:00] [Client thread/WARN]: Skipping bad option")
+        .unwrap();
+    assert_eq!(record.level, LogLevel::Warn);
+    assert_eq!(record.thread, "Client thread");
+    assert_eq!(record.message, "Skipping bad option");
+    assert!(record.level >= LogLevel::Warn);
+
+    let record = parser.feed("Exception in thread \"main\"").unwrap();
+    assert_eq!(record.level, LogLevel::Info);
+    assert_eq!(record.message, "Exception in thread \"main\"");
+
+    // An event interrupted by killing the game.
+    assert!(
+        parser
+            .feed(r#"<log4j:Event logger="a" timestamp="1" level="INFO" thread="main">"#)
+            .is_none()
+    );
+    assert!(parser.finish().unwrap().message.starts_with("<log4j:Event"));
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::{Request, Response};
+use gridcore::constants::DownloadSource;
+use gridcore::utils::maven::{self, MavenCoordinate, ResolvedArtifact};
+
+const SHA1: &str = "a2c8f1ae6a1a9e1d3b4f6c8e0b2d4f6a8c0e2b4d";
+
+fn stand_in(request: Request) -> Response {
+    match request.path.as_str() {
+        "/mirror/net/fabricmc/fabric-loader/0.16.14/fabric-loader-0.16.14.jar.sha1" => {
+            Response::new(200, format!("{}  fabric-loader-0.16.14.jar\n", SHA1))
+        }
+        "/broken/net/fabricmc/fabric-loader/0.16.14/fabric-loader-0.16.14.jar.sha1" => {
+            Response::new(200, "<html>Not a hash</html>")
+        }
+        _ => Response::new(404, "Not Found"),
+    }
+}
+
+#[test]
+fn maven_coordinate() {
+    let coordinate = MavenCoordinate::parse("org.lwjgl:lwjgl:3.3.3:natives-linux").unwrap();
+    assert_eq!(coordinate.directory(), "org/lwjgl/lwjgl/3.3.3");
+    assert_eq!(coordinate.file_name(), "lwjgl-3.3.3-natives-linux.jar");
+    assert_eq!(
+        coordinate.url("https://libraries.minecraft.net/"),
+        "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.3/lwjgl-3.3.3-natives-linux.jar"
+    );
+    assert_eq!(
+        coordinate.to_string(),
+        "org.lwjgl:lwjgl:3.3.3:natives-linux"
+    );
+
+    let coordinate = MavenCoordinate::parse("de.oceanlabs.mcp:mcp_config:1.20.1@zip").unwrap();
+    assert_eq!(
+        coordinate.path(),
+        "de/oceanlabs/mcp/mcp_config/1.20.1/mcp_config-1.20.1.zip"
+    );
+    assert_eq!(
+        coordinate.to_string(),
+        "de.oceanlabs.mcp:mcp_config:1.20.1@zip"
+    );
+
+    assert_eq!(MavenCoordinate::parse("org.ow2.asm:asm"), None);
+    assert_eq!(MavenCoordinate::parse("org.ow2.asm::9.8"), None);
+    assert_eq!(MavenCoordinate::parse("org.ow2.asm:asm:9.8@"), None);
+    assert_eq!(MavenCoordinate::parse("a:b:c:d:e"), None);
+}
+
+#[test]
+fn mirrored_url() {
+    let mirrored = [
+        (
+            "https://libraries.minecraft.net/org/ow2/asm/asm/9.8/asm-9.8.jar",
+            "https://bmclapi2.bangbang93.com/maven/org/ow2/asm/asm/9.8/asm-9.8.jar",
+        ),
+        (
+            "https://maven.minecraftforge.net/net/minecraftforge/forge/1.20.1-47.4.0/forge-1.20.1-47.4.0-installer.jar",
+            "https://bmclapi2.bangbang93.com/maven/net/minecraftforge/forge/1.20.1-47.4.0/forge-1.20.1-47.4.0-installer.jar",
+        ),
+        (
+            "http://files.minecraftforge.net/maven/org/scala-lang/scala-library/2.11.1/scala-library-2.11.1.jar",
+            "https://bmclapi2.bangbang93.com/maven/org/scala-lang/scala-library/2.11.1/scala-library-2.11.1.jar",
+        ),
+        (
+            "http://repo.mumfrey.com/content/repositories/snapshots/com/mumfrey/liteloader/1.12.2-SNAPSHOT/liteloader-1.12.2-SNAPSHOT.jar",
+            "https://bmclapi2.bangbang93.com/maven/com/mumfrey/liteloader/1.12.2-SNAPSHOT/liteloader-1.12.2-SNAPSHOT.jar",
+        ),
+        (
+            "http://dl.liteloader.com/versions/com/mumfrey/liteloader/1.10.2/liteloader-1.10.2.jar",
+            "https://bmclapi2.bangbang93.com/maven/com/mumfrey/liteloader/1.10.2/liteloader-1.10.2.jar",
+        ),
+    ];
+
+    for (url, mirror) in mirrored {
+        assert_eq!(maven::mirror_url(url, DownloadSource::Official), url);
+        assert_eq!(maven::mirror_url(url, DownloadSource::Bangbang93), mirror);
+    }
+
+    // BMCLAPI doesn't mirror Quilt's repository.
+    let quilt = "https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-loader/0.29.0/quilt-loader-0.29.0.jar";
+    assert_eq!(maven::mirror_url(quilt, DownloadSource::Bangbang93), quilt);
+}
+
+#[tokio::test]
+async fn resolve_artifact() {
+    let homepage = common::serve(stand_in).await;
+    let coordinate = MavenCoordinate::parse("net.fabricmc:fabric-loader:0.16.14").unwrap();
+
+    // The first repository doesn't have the artifact, and the second one publishes a malformed hash.
+    let repositories =
+        ["missing", "broken", "mirror"].map(|repository| format!("{}/{}/", homepage, repository));
+
+    assert_eq!(
+        maven::resolve(&coordinate, &repositories).await,
+        Some(ResolvedArtifact {
+            url: format!(
+                "{}/mirror/net/fabricmc/fabric-loader/0.16.14/fabric-loader-0.16.14.jar",
+                homepage
+            ),
+            sha1: Some(SHA1.to_string())
+        })
+    );
+    assert_eq!(maven::resolve(&coordinate, &repositories[..2]).await, None);
+}
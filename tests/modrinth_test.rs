@@ -0,0 +1,325 @@
+mod common;
+
+use common::{Request, Response};
+use gridcore::error_handling::ModrinthError;
+use gridcore::managers::mods::modrinth::{ModrinthClient, SearchQuery};
+
+use std::env;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde_json::{Value, json};
+use sha2::{Digest, Sha512};
+
+/// The base URL of the stand-in, which the URLs of files point to.
+static HOMEPAGE: OnceLock<String> = OnceLock::new();
+
+fn sha512_of(bytes: &[u8]) -> String {
+    format!("{:x}", Sha512::digest(bytes))
+}
+
+fn version(id: &str, project: &str, loaders: &[&str], dependencies: Value) -> Value {
+    json!({
+        "id": id,
+        "project_id": project,
+        "name": project,
+        "version_number": id,
+        "version_type": "release",
+        "game_versions": ["1.21.5"],
+        "loaders": loaders,
+        "dependencies": dependencies,
+        "files": [
+            {
+                "hashes": { "sha1": "", "sha512": sha512_of(id.as_bytes()) },
+                "url": format!(
+                    "{}/files/{}.jar",
+                    HOMEPAGE.get().map(String::as_str).unwrap_or_default(),
+                    id
+                ),
+                "filename": format!("{}.jar", id),
+                "primary": true,
+                "size": id.len()
+            }
+        ]
+    })
+}
+
+/// The versions which can be requested by their IDs, or identified by the hashes of their files.
+fn known_version(id: &str) -> Option<Value> {
+    Some(match id {
+        "api-2" => version("api-2", "api", &["fabric"], json!([])),
+        "mod-a-1" => version(
+            "mod-a-1",
+            "mod-a",
+            &["fabric", "quilt"],
+            json!([
+                { "project_id": "api", "dependency_type": "required" },
+                { "project_id": "other", "dependency_type": "incompatible" },
+                { "project_id": "extra", "dependency_type": "optional" }
+            ]),
+        ),
+        // The newer version is compatible with `other`, and requires it.
+        "mod-a-2" => version(
+            "mod-a-2",
+            "mod-a",
+            &["fabric"],
+            json!([
+                { "project_id": "api", "dependency_type": "required" },
+                { "version_id": "other-1", "project_id": "other", "dependency_type": "required" }
+            ]),
+        ),
+        "other-1" => version("other-1", "other", &["fabric"], json!([])),
+        "mod-b-1" => version("mod-b-1", "mod-b", &["forge"], json!([])),
+        "mod-c-1" => version(
+            "mod-c-1",
+            "mod-c",
+            &["fabric"],
+            json!([{ "version_id": "api-0", "project_id": "api", "dependency_type": "required" }]),
+        ),
+        "api-0" => version(
+            "api-0",
+            "api",
+            &["fabric"],
+            json!([{ "project_id": "mod-c", "dependency_type": "incompatible" }]),
+        ),
+        "mod-d-1" => version(
+            "mod-d-1",
+            "mod-d",
+            &["fabric"],
+            json!([{ "project_id": "missing", "dependency_type": "required" }]),
+        ),
+        "mod-e-1" => version(
+            "mod-e-1",
+            "mod-e",
+            &["fabric"],
+            json!([{ "project_id": "api", "dependency_type": "required" }]),
+        ),
+        "mod-f-1" => version(
+            "mod-f-1",
+            "mod-f",
+            &["fabric"],
+            json!([{ "version_id": "evil-1", "project_id": "evil", "dependency_type": "required" }]),
+        ),
+        // The file would be written outside the `mods` folder.
+        "evil-1" => {
+            let mut version = version("evil-1", "evil", &["fabric"], json!([]));
+            version["files"][0]["filename"] = json!("../evil-1.jar");
+            version
+        }
+        _ => return None,
+    })
+}
+
+fn stand_in(request: Request) -> Response {
+    let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+
+    let body = match (request.method.as_str(), path) {
+        ("GET", "/v2/search") => {
+            if !query.contains("%22versions%3A1.21.5%22")
+                || !query.contains("%22categories%3Afabric%22")
+                || !query.contains("%22categories%3Aoptimization%22")
+            {
+                return Response::new(400, "Unexpected facets");
+            }
+
+            json!({
+                "hits": [{
+                    "project_id": "AANobbMI",
+                    "slug": "sodium",
+                    "title": "Sodium",
+                    "description": "The fastest rendering optimization mod",
+                    "categories": ["fabric", "optimization"],
+                    "project_type": "mod",
+                    "downloads": 1000,
+                    "versions": ["1.21.5"]
+                }],
+                "offset": 0,
+                "limit": 10,
+                "total_hits": 1
+            })
+        }
+        ("GET", "/v2/project/api/version") if query.contains("%22fabric%22") => json!([
+            version("api-2", "api", &["fabric"], json!([])),
+            version("api-1", "api", &["fabric"], json!([]))
+        ]),
+        ("GET", "/v2/project/missing/version") => json!([]),
+        ("GET", version) if version.starts_with("/v2/version/") => {
+            match known_version(version.trim_start_matches("/v2/version/")) {
+                Some(version) => version,
+                None => return Response::new(404, ""),
+            }
+        }
+        ("POST", "/v2/version_files") => Value::Object(
+            ["api-2", "mod-a-1", "mod-a-2", "other-1"]
+                .into_iter()
+                .filter(|id| request.body.contains(&sha512_of(id.as_bytes())))
+                .map(|id| (sha512_of(id.as_bytes()), known_version(id).unwrap()))
+                .collect(),
+        ),
+        // The file of `mod-e-1` is corrupted.
+        ("GET", "/files/mod-e-1.jar") => return Response::new(200, "corrupted"),
+        ("GET", file) if file.starts_with("/files/") => {
+            let id = file.trim_start_matches("/files/").trim_end_matches(".jar");
+            return Response::new(200, id);
+        }
+        _ => return Response::new(404, ""),
+    };
+
+    Response::new(200, body.to_string()).header("Content-Type", "application/json")
+}
+
+async fn jars(mods_dir: &Path) -> Vec<String> {
+    let mut entries = tokio::fs::read_dir(mods_dir).await.unwrap();
+    let mut jars = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await.unwrap() {
+        jars.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    jars.sort();
+
+    jars
+}
+
+#[tokio::test]
+async fn modrinth_search() {
+    let homepage = common::serve(stand_in).await;
+    let client = ModrinthClient::with_api_root(&format!("{}/v2/", homepage));
+    assert_eq!(client.api_root(), format!("{}/v2", homepage));
+
+    let results = client
+        .search(&SearchQuery {
+            query: "sodium".to_string(),
+            game_version: Some("1.21.5".to_string()),
+            loader: Some("fabric".to_string()),
+            categories: vec!["optimization".to_string()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(results.total_hits, 1);
+    assert_eq!(results.hits[0].slug, "sodium");
+
+    let versions = client
+        .list_versions("api", "1.21.5", "fabric")
+        .await
+        .unwrap();
+    assert_eq!(versions[0].id, "api-2");
+    assert!(versions[0].files[0].primary);
+
+    assert!(matches!(
+        client.get_version("unknown").await,
+        Err(ModrinthError::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn modrinth_install() {
+    let homepage = common::serve(stand_in).await;
+    HOMEPAGE.set(homepage.clone()).unwrap();
+
+    let client = ModrinthClient::with_api_root(&format!("{}/v2", homepage));
+    let mods_dir = env::temp_dir().join(format!("gridcore-modrinth-{}", std::process::id()));
+    let _ = tokio::fs::remove_dir_all(&mods_dir).await;
+
+    // Nothing is written when the loader, a dependency or a file is wrong.
+    assert!(matches!(
+        client
+            .install("mod-b-1", "1.21.5", "fabric", &mods_dir)
+            .await,
+        Err(ModrinthError::MissingLoader(_))
+    ));
+    assert!(matches!(
+        client
+            .install("mod-c-1", "1.21.5", "fabric", &mods_dir)
+            .await,
+        Err(ModrinthError::Incompatible(_))
+    ));
+    assert!(matches!(
+        client
+            .install("mod-d-1", "1.21.5", "fabric", &mods_dir)
+            .await,
+        Err(ModrinthError::NoCompatibleVersion(_))
+    ));
+    assert!(matches!(
+        client
+            .install("mod-e-1", "1.21.5", "fabric", &mods_dir)
+            .await,
+        Err(ModrinthError::CheckIntegrityError(_))
+    ));
+    assert!(matches!(
+        client
+            .install("mod-f-1", "1.21.5", "fabric", &mods_dir)
+            .await,
+        Err(ModrinthError::InvalidFileName(name)) if name == "../evil-1.jar"
+    ));
+    assert!(!mods_dir.exists());
+
+    // The newest version of the required project is installed, and the optional one is not.
+    let paths = client
+        .install("mod-a-1", "1.21.5", "fabric", &mods_dir)
+        .await
+        .unwrap();
+    assert_eq!(
+        paths,
+        [mods_dir.join("mod-a-1.jar"), mods_dir.join("api-2.jar")]
+    );
+    assert_eq!(
+        tokio::fs::read(mods_dir.join("api-2.jar")).await.unwrap(),
+        b"api-2"
+    );
+
+    // `api-2` is already installed, so only `mod-a` is installed again.
+    let mut installed = client
+        .installed_versions(&mods_dir)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.path, m.version))
+        .collect::<Vec<_>>();
+    installed.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(installed.len(), 2);
+    assert_eq!(installed[0].0, mods_dir.join("api-2.jar"));
+    assert_eq!(installed[1].1.id, "mod-a-1");
+
+    let installed = installed.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+    let version = client.get_version("mod-a-1").await.unwrap();
+    let plan = client
+        .resolve(version, "1.21.5", "fabric", &installed)
+        .await
+        .unwrap();
+    assert_eq!(plan.len(), 1);
+
+    // `other` is incompatible with the installed `mod-a-1`.
+    assert!(matches!(
+        client
+            .install("other-1", "1.21.5", "fabric", &mods_dir)
+            .await,
+        Err(ModrinthError::Incompatible(_))
+    ));
+
+    // `mod-a-2` replaces `mod-a-1`, whose incompatibility no longer matters.
+    let paths = client
+        .install("mod-a-2", "1.21.5", "fabric", &mods_dir)
+        .await
+        .unwrap();
+    assert_eq!(
+        paths,
+        [mods_dir.join("mod-a-2.jar"), mods_dir.join("other-1.jar")]
+    );
+    assert_eq!(
+        jars(&mods_dir).await,
+        ["api-2.jar", "mod-a-2.jar", "other-1.jar"]
+    );
+
+    // Installing the same version again overwrites its jar.
+    client
+        .install("mod-a-2", "1.21.5", "fabric", &mods_dir)
+        .await
+        .unwrap();
+    assert_eq!(
+        jars(&mods_dir).await,
+        ["api-2.jar", "mod-a-2.jar", "other-1.jar"]
+    );
+
+    tokio::fs::remove_dir_all(&mods_dir).await.unwrap();
+}
@@ -0,0 +1,856 @@
+mod common;
+
+use common::{Request, Response};
+use gridcore::constants::{Category, DownloadSource};
+use gridcore::error_handling::InstallError;
+use gridcore::managers::mods::LoaderVersion;
+use gridcore::managers::mods::fabric::{self, FabricInstaller};
+use gridcore::managers::mods::forge::{self, ForgeInstaller};
+use gridcore::managers::mods::liteloader::{self, LiteLoaderInstaller};
+use gridcore::managers::mods::neoforge::{self, NeoForgeInstaller};
+use gridcore::managers::mods::optifine;
+use gridcore::managers::mods::quilt;
+use gridcore::managers::version;
+
+use std::collections::HashMap;
+use std::env;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// Archives the entries in memory, e.g. to build an installer jar.
+fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (name, contents) in entries {
+        writer
+            .start_file(*name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(contents).unwrap();
+    }
+
+    writer.finish().unwrap().into_inner()
+}
+
+async fn write_installer(path: &Path, profile: &Value, entries: &[(&str, &[u8])]) {
+    let profile = profile.to_string();
+    let mut entries = entries.to_vec();
+    entries.push(("install_profile.json", profile.as_bytes()));
+
+    tokio::fs::create_dir_all(path.parent().unwrap())
+        .await
+        .unwrap();
+    tokio::fs::write(path, zip(&entries)).await.unwrap();
+}
+
+#[test]
+fn fabric_loader_versions() {
+    let versions = fabric::loader_versions_from(&json!([
+        {
+            "loader": {
+                "separator": ".",
+                "build": 15,
+                "maven": "net.fabricmc:fabric-loader:0.17.0-beta.1",
+                "version": "0.17.0-beta.1",
+                "stable": false
+            },
+            "intermediary": { "maven": "net.fabricmc:intermediary:1.21.5", "version": "1.21.5", "stable": true }
+        },
+        {
+            "loader": {
+                "separator": ".",
+                "build": 14,
+                "maven": "net.fabricmc:fabric-loader:0.16.14",
+                "version": "0.16.14",
+                "stable": true
+            },
+            "intermediary": { "maven": "net.fabricmc:intermediary:1.21.5", "version": "1.21.5", "stable": true }
+        }
+    ]));
+
+    assert_eq!(
+        versions,
+        [
+            LoaderVersion {
+                version: "0.17.0-beta.1".to_string(),
+                stable: false
+            },
+            LoaderVersion {
+                version: "0.16.14".to_string(),
+                stable: true
+            }
+        ]
+    );
+
+    // Unknown game versions have no loaders.
+    assert!(fabric::loader_versions_from(&json!([])).is_empty());
+}
+
+#[test]
+fn quilt_version_json() {
+    let metadata = json!({
+        "loader": {
+            "separator": ".",
+            "build": 0,
+            "maven": "org.quiltmc:quilt-loader:0.29.0",
+            "version": "0.29.0"
+        },
+        "hashed": { "maven": "org.quiltmc:hashed:1.21.5", "version": "1.21.5" },
+        "intermediary": { "maven": "net.fabricmc:intermediary:1.21.5", "version": "1.21.5" },
+        "launcherMeta": {
+            "version": 1,
+            "libraries": {
+                "client": [],
+                "common": [{ "name": "org.ow2.asm:asm:9.8", "url": "https://maven.fabricmc.net/" }],
+                "server": [{ "name": "net.fabricmc:sponge-mixin:0.15.5", "url": "https://maven.fabricmc.net/" }]
+            },
+            "mainClass": {
+                "client": "org.quiltmc.loader.impl.launch.knot.KnotClient",
+                "server": "org.quiltmc.loader.impl.launch.knot.KnotServer"
+            }
+        }
+    });
+
+    let data = quilt::version_json_from(&metadata, "1.21.5", Category::Client).unwrap();
+    assert_eq!(data["id"], "quilt-loader-0.29.0-1.21.5");
+    assert_eq!(data["inheritsFrom"], "1.21.5");
+    assert_eq!(
+        data["mainClass"],
+        "org.quiltmc.loader.impl.launch.knot.KnotClient"
+    );
+    assert_eq!(
+        data["libraries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|lib| lib["name"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        [
+            "org.ow2.asm:asm:9.8",
+            "org.quiltmc:hashed:1.21.5",
+            "org.quiltmc:quilt-loader:0.29.0"
+        ]
+    );
+
+    let data = quilt::version_json_from(&metadata, "1.21.5", Category::Server).unwrap();
+    assert_eq!(data["id"], "quilt-loader-0.29.0-1.21.5-server");
+    assert_eq!(data["libraries"].as_array().unwrap().len(), 4);
+
+    assert!(quilt::version_json_from(&json!({}), "1.21.5", Category::Client).is_err());
+
+    let versions = quilt::loader_versions_from(&json!([
+        { "loader": { "version": "0.29.1-beta.1" } },
+        { "loader": { "version": "0.29.0" } }
+    ]));
+    assert!(!versions[0].stable);
+    assert!(versions[1].stable);
+}
+
+#[test]
+fn forge_promoted_versions() {
+    let data = json!({
+        "homepage": "https://files.minecraftforge.net/net/minecraftforge/forge/",
+        "promos": {
+            "1.20.1-latest": "47.4.1",
+            "1.20.1-recommended": "47.4.0",
+            "1.21.5-latest": "55.0.21"
+        }
+    });
+
+    assert_eq!(
+        forge::promoted_versions_from(&data, "1.20.1"),
+        [
+            LoaderVersion {
+                version: "47.4.0".to_string(),
+                stable: true
+            },
+            LoaderVersion {
+                version: "47.4.1".to_string(),
+                stable: false
+            }
+        ]
+    );
+    assert_eq!(
+        forge::promoted_versions_from(&data, "1.21.5"),
+        [LoaderVersion {
+            version: "55.0.21".to_string(),
+            stable: false
+        }]
+    );
+    assert!(forge::promoted_versions_from(&data, "1.7.2").is_empty());
+}
+
+#[test]
+fn neoforge_versions() {
+    assert_eq!(neoforge::version_prefix("1.21.1").as_deref(), Some("21.1."));
+    assert_eq!(neoforge::version_prefix("1.21").as_deref(), Some("21.0."));
+    assert_eq!(
+        neoforge::version_prefix("1.20.1").as_deref(),
+        Some("1.20.1-")
+    );
+    assert_eq!(neoforge::version_prefix("1.19.2"), None);
+    assert_eq!(neoforge::version_prefix("25w14a"), None);
+
+    let metadata = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>net.neoforged</groupId>
+  <artifactId>neoforge</artifactId>
+  <versioning>
+    <latest>21.5.0-beta</latest>
+    <release>21.5.0-beta</release>
+    <versions>
+      <version>21.0.167</version>
+      <version>21.1.171</version>
+      <version>21.1.172</version>
+      <version>21.10.1-beta</version>
+      <version>21.5.0-beta</version>
+    </versions>
+  </versioning>
+</metadata>"#;
+
+    assert_eq!(
+        neoforge::versions_from(metadata, "1.21.1"),
+        [
+            LoaderVersion {
+                version: "21.1.172".to_string(),
+                stable: true
+            },
+            LoaderVersion {
+                version: "21.1.171".to_string(),
+                stable: true
+            }
+        ]
+    );
+    assert_eq!(
+        neoforge::versions_from(metadata, "1.21.5"),
+        [LoaderVersion {
+            version: "21.5.0-beta".to_string(),
+            stable: false
+        }]
+    );
+
+    // NeoForge for 1.20.1 is numbered like Forge.
+    let metadata =
+        "<versions><version>1.20.1-47.1.105</version><version>1.20.1-47.1.106</version></versions>";
+    assert_eq!(
+        neoforge::versions_from(metadata, "1.20.1")[0].version,
+        "47.1.106"
+    );
+}
+
+#[test]
+fn liteloader_version_json() {
+    let data = json!({
+        "versions": {
+            "1.12.2": {
+                "repo": { "url": "http://repo.mumfrey.com/content/repositories/snapshots/" },
+                "snapshots": {
+                    "libraries": [
+                        { "name": "net.minecraft:launchwrapper:1.12" },
+                        { "name": "org.ow2.asm:asm-all:5.2" }
+                    ],
+                    "com.mumfrey:liteloader": {
+                        "latest": { "version": "1.12.2-SNAPSHOT", "timestamp": "1531059153" },
+                        "1.12.2-SNAPSHOT": { "version": "1.12.2-SNAPSHOT", "timestamp": "1531059153" }
+                    }
+                }
+            },
+            "1.10.2": {
+                "repo": { "url": "http://dl.liteloader.com/versions/" },
+                "artefacts": {
+                    "com.mumfrey:liteloader": {
+                        "1.10.2": {
+                            "version": "1.10.2",
+                            "timestamp": "1474472539",
+                            "tweakClass": "com.mumfrey.liteloader.launch.LiteLoaderTweaker",
+                            "libraries": [{ "name": "net.minecraft:launchwrapper:1.12" }]
+                        }
+                    }
+                },
+                "snapshots": {
+                    "com.mumfrey:liteloader": {
+                        "1.10.2-SNAPSHOT": { "version": "1.10.2-SNAPSHOT", "timestamp": "1481228740" }
+                    }
+                }
+            }
+        }
+    });
+
+    assert_eq!(
+        liteloader::versions_from(&data, "1.10.2"),
+        [
+            LoaderVersion {
+                version: "1.10.2-SNAPSHOT".to_string(),
+                stable: false
+            },
+            LoaderVersion {
+                version: "1.10.2".to_string(),
+                stable: true
+            }
+        ]
+    );
+
+    let parent = json!({
+        "id": "1.12.2",
+        "type": "release",
+        "mainClass": "net.minecraft.client.main.Main",
+        "minecraftArguments": "--username ${auth_player_name} --version ${version_name}"
+    });
+    let version =
+        liteloader::version_json_from(&data, &parent, "1.12.2", "1.12.2-SNAPSHOT").unwrap();
+
+    assert_eq!(version["id"], "1.12.2-liteloader-1.12.2-SNAPSHOT");
+    assert_eq!(version["inheritsFrom"], "1.12.2");
+    assert_eq!(version["mainClass"], "net.minecraft.launchwrapper.Launch");
+    // Legacy arguments replace the parent's ones, so they must be repeated.
+    assert_eq!(
+        version["minecraftArguments"],
+        "--username ${auth_player_name} --version ${version_name} \
+         --tweakClass com.mumfrey.liteloader.launch.LiteLoaderTweaker"
+    );
+    assert_eq!(
+        version["libraries"],
+        json!([
+            {
+                "name": "com.mumfrey:liteloader:1.12.2-SNAPSHOT",
+                "url": "http://repo.mumfrey.com/content/repositories/snapshots/"
+            },
+            { "name": "net.minecraft:launchwrapper:1.12", "url": "https://libraries.minecraft.net/" },
+            { "name": "org.ow2.asm:asm-all:5.2", "url": "https://libraries.minecraft.net/" }
+        ])
+    );
+
+    assert!(liteloader::version_json_from(&data, &parent, "1.12.2", "1.12.1-SNAPSHOT").is_err());
+}
+
+#[test]
+fn optifine_version_json() {
+    assert_eq!(
+        optifine::versions_from(&json!([
+            { "mcversion": "1.12.2", "type": "HD_U", "patch": "G5", "filename": "OptiFine_1.12.2_HD_U_G5.jar" },
+            { "mcversion": "1.12.2", "type": "HD_U", "patch": "G6_pre1", "filename": "preview_OptiFine_1.12.2_HD_U_G6_pre1.jar" }
+        ])),
+        [
+            LoaderVersion {
+                version: "HD_U_G5".to_string(),
+                stable: true
+            },
+            LoaderVersion {
+                version: "HD_U_G6_pre1".to_string(),
+                stable: false
+            }
+        ]
+    );
+
+    let vanilla = json!({
+        "id": "1.12.2",
+        "mainClass": "net.minecraft.client.main.Main",
+        "minecraftArguments": "--username ${auth_player_name}",
+        "libraries": [{ "name": "com.mojang:patchy:1.3.9" }]
+    });
+    let version = optifine::version_json_from(&vanilla, "1.12.2", "HD_U_G5", Some("1.12")).unwrap();
+
+    assert_eq!(version["id"], "1.12.2-optifine-HD_U_G5");
+    assert_eq!(
+        version["minecraftArguments"],
+        "--username ${auth_player_name} --tweakClass optifine.OptiFineTweaker"
+    );
+    assert_eq!(
+        version["libraries"],
+        json!([
+            { "name": "optifine:OptiFine:1.12.2_HD_U_G5" },
+            { "name": "optifine:launchwrapper-of:1.12" }
+        ])
+    );
+
+    // Forge loads LaunchWrapper itself, and OptiFine is tweaked after it.
+    let forge = json!({
+        "id": "1.12.2-forge-14.23.5.2860",
+        "mainClass": "net.minecraft.launchwrapper.Launch",
+        "minecraftArguments": "--username ${auth_player_name} \
+             --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker",
+        "libraries": [
+            { "name": "net.minecraftforge:forge:1.12.2-14.23.5.2860" },
+            { "name": "net.minecraft:launchwrapper:1.12" }
+        ]
+    });
+    let version = optifine::version_json_from(&forge, "1.12.2", "HD_U_G5", None).unwrap();
+
+    assert_eq!(version["inheritsFrom"], "1.12.2-forge-14.23.5.2860");
+    assert_eq!(
+        version["minecraftArguments"],
+        "--username ${auth_player_name} \
+         --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker \
+         --tweakClass optifine.OptiFineForgeTweaker"
+    );
+    assert_eq!(
+        version["libraries"],
+        json!([{ "name": "optifine:OptiFine:1.12.2_HD_U_G5" }])
+    );
+
+    // Modern Forge doesn't use LaunchWrapper, so OptiFine must be a mod there.
+    let forge = json!({
+        "id": "1.20.1-forge-47.4.0",
+        "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+        "arguments": { "game": [] },
+        "libraries": [{ "name": "net.minecraftforge:forge:1.20.1-47.4.0" }]
+    });
+    assert!(optifine::version_json_from(&forge, "1.20.1", "HD_U_I6", None).is_err());
+}
+
+#[test]
+fn inherited_version() {
+    let parent = json!({
+        "id": "1.21.5",
+        "mainClass": "net.minecraft.client.main.Main",
+        "assets": "24",
+        "arguments": {
+            "game": ["--username", "${auth_player_name}"],
+            "jvm": ["-cp", "${classpath}"]
+        },
+        "libraries": [
+            { "name": "org.ow2.asm:asm:9.3" },
+            { "name": "org.lwjgl:lwjgl:3.3.3" },
+            { "name": "org.lwjgl:lwjgl:3.3.3:natives-linux" }
+        ]
+    });
+    let child = json!({
+        "id": "fabric-loader-0.16.14-1.21.5",
+        "inheritsFrom": "1.21.5",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": {
+            "game": [],
+            "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]
+        },
+        "libraries": [
+            { "name": "org.ow2.asm:asm:9.8", "url": "https://maven.fabricmc.net/" },
+            { "name": "net.fabricmc:fabric-loader:0.16.14", "url": "https://maven.fabricmc.net/" }
+        ]
+    });
+
+    let data = version::merge_version_json(parent, &child);
+
+    assert_eq!(data["id"], "fabric-loader-0.16.14-1.21.5");
+    assert_eq!(
+        data["mainClass"],
+        "net.fabricmc.loader.impl.launch.knot.KnotClient"
+    );
+    assert_eq!(data["assets"], "24");
+    assert_eq!(data["jar"], "1.21.5");
+    assert_eq!(data.get("inheritsFrom"), None);
+    assert_eq!(
+        data["arguments"]["jvm"],
+        json!([
+            "-cp",
+            "${classpath}",
+            "-DFabricMcEmu= net.minecraft.client.main.Main "
+        ])
+    );
+    assert_eq!(
+        data["arguments"]["game"],
+        json!(["--username", "${auth_player_name}"])
+    );
+    // The newer ASM of the loader replaces the one of the game.
+    assert_eq!(
+        data["libraries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|lib| lib["name"].as_str().unwrap())
+            .collect::<Vec<_>>(),
+        [
+            "org.ow2.asm:asm:9.8",
+            "net.fabricmc:fabric-loader:0.16.14",
+            "org.lwjgl:lwjgl:3.3.3",
+            "org.lwjgl:lwjgl:3.3.3:natives-linux"
+        ]
+    );
+}
+
+#[test]
+fn forge_substitute() {
+    let data = HashMap::from([(
+        "MINECRAFT_JAR".to_string(),
+        "/game/versions/1.20.1/1.20.1.jar".to_string(),
+    )]);
+
+    assert_eq!(
+        forge::substitute(
+            "[net.minecraftforge:forge:1.20.1-47.4.0:client]",
+            "/game",
+            &data
+        )
+        .unwrap(),
+        "/game/libraries/net/minecraftforge/forge/1.20.1-47.4.0/forge-1.20.1-47.4.0-client.jar"
+    );
+    assert_eq!(
+        forge::substitute(
+            "[de.oceanlabs.mcp:mcp_config:1.20.1:mappings@txt]",
+            "/game",
+            &data
+        )
+        .unwrap(),
+        "/game/libraries/de/oceanlabs/mcp/mcp_config/1.20.1/mcp_config-1.20.1-mappings.txt"
+    );
+    assert_eq!(
+        forge::substitute("'{MINECRAFT_JAR}'", "/game", &data).unwrap(),
+        "{MINECRAFT_JAR}"
+    );
+    assert_eq!(
+        forge::substitute("--input={MINECRAFT_JAR}", "/game", &data).unwrap(),
+        "--input=/game/versions/1.20.1/1.20.1.jar"
+    );
+    assert!(matches!(
+        forge::substitute("{MINECRAFT_JAR}{SIDE}", "/game", &data),
+        Err(InstallError::InvalidInstaller(_))
+    ));
+}
+
+#[tokio::test]
+async fn forge_install_with_processors() {
+    let root = env::temp_dir().join(format!("gridcore-forge-{}", std::process::id()));
+    let _ = tokio::fs::remove_dir_all(&root).await;
+    let installer = root.join("forge-1.20.1-47.4.0-installer.jar");
+    let libraries = root.join("libraries/net/minecraftforge");
+    let patched = libraries.join("forge/1.20.1-47.4.0/forge-1.20.1-47.4.0-client.jar");
+
+    let tool = zip(&[(
+        "META-INF/MANIFEST.MF",
+        b"Manifest-Version: 1.0\r\nMain-Class: net.minecraftforge.installertools.ConsoleTool\r\n",
+    )]);
+    let version = json!({
+        "id": "1.20.1-forge-47.4.0",
+        "inheritsFrom": "1.20.1",
+        "mainClass": "cpw.mods.bootstraplauncher.BootstrapLauncher",
+        "libraries": [{
+            "name": "net.minecraftforge:forge:1.20.1-47.4.0",
+            "downloads": { "artifact": {
+                "path": "net/minecraftforge/forge/1.20.1-47.4.0/forge-1.20.1-47.4.0.jar",
+                "url": ""
+            } }
+        }]
+    })
+    .to_string();
+    let profile = json!({
+        "minecraft": "1.20.1",
+        "json": "/version.json",
+        "libraries": [{
+            "name": "net.minecraftforge:installertools:1.0",
+            "downloads": { "artifact": {
+                "path": "net/minecraftforge/installertools/1.0/installertools-1.0.jar",
+                "url": ""
+            } }
+        }],
+        "data": {
+            "PATCHED": {
+                "client": "[net.minecraftforge:forge:1.20.1-47.4.0:client]",
+                "server": "[net.minecraftforge:forge:1.20.1-47.4.0:server]"
+            },
+            "PATCHED_SHA": {
+                "client": format!("'{:x}'", Sha1::digest(b"patched")),
+                "server": format!("'{:x}'", Sha1::digest(b"patched"))
+            },
+            "BINPATCH": { "client": "/data/client.lzma", "server": "/data/server.lzma" }
+        },
+        "processors": [
+            {
+                "sides": ["client"],
+                "jar": "net.minecraftforge:installertools:1.0",
+                "classpath": [],
+                "args": [
+                    "--task", "'PATCH'",
+                    "--input", "{MINECRAFT_JAR}",
+                    "--patch", "{BINPATCH}",
+                    "--output", "{PATCHED}"
+                ],
+                "outputs": { "{PATCHED}": "{PATCHED_SHA}" }
+            },
+            // Clients skip it, otherwise it would fail since its jar doesn't exist.
+            { "sides": ["server"], "jar": "net.minecraftforge:missing:1.0" }
+        ]
+    });
+    write_installer(
+        &installer,
+        &profile,
+        &[
+            ("version.json", version.as_bytes()),
+            ("data/client.lzma", b"client"),
+            ("data/server.lzma", b"server"),
+            (
+                "maven/net/minecraftforge/installertools/1.0/installertools-1.0.jar",
+                &tool,
+            ),
+            (
+                "maven/net/minecraftforge/forge/1.20.1-47.4.0/forge-1.20.1-47.4.0.jar",
+                b"forge",
+            ),
+        ],
+    )
+    .await;
+    let root = root.to_str().unwrap();
+
+    // Processors patch the vanilla jar, which must be downloaded first.
+    assert!(matches!(
+        forge::install_into(&installer, root, DownloadSource::Official, Category::Client).await,
+        Err(InstallError::ProcessorError(_))
+    ));
+
+    let game = Path::new(root).join("versions/1.20.1");
+    tokio::fs::create_dir_all(&game).await.unwrap();
+    tokio::fs::write(game.join("1.20.1.jar"), b"vanilla")
+        .await
+        .unwrap();
+
+    // The output is in place, so the processor is skipped.
+    tokio::fs::create_dir_all(patched.parent().unwrap())
+        .await
+        .unwrap();
+    tokio::fs::write(&patched, b"patched").await.unwrap();
+
+    let id = forge::install_into(&installer, root, DownloadSource::Official, Category::Client)
+        .await
+        .unwrap();
+    assert_eq!(id, "1.20.1-forge-47.4.0");
+    assert!(
+        Path::new(root)
+            .join("versions/1.20.1-forge-47.4.0/1.20.1-forge-47.4.0.json")
+            .exists()
+    );
+    assert!(
+        libraries
+            .join("installertools/1.0/installertools-1.0.jar")
+            .exists()
+    );
+    assert_eq!(
+        tokio::fs::read(libraries.join("forge/1.20.1-47.4.0/forge-1.20.1-47.4.0.jar"))
+            .await
+            .unwrap(),
+        b"forge"
+    );
+
+    // The output doesn't match its SHA1, so the processor runs, and fails without its classes.
+    tokio::fs::write(&patched, b"stale").await.unwrap();
+    assert!(matches!(
+        forge::install_into(&installer, root, DownloadSource::Official, Category::Client).await,
+        Err(InstallError::ProcessorError(_))
+    ));
+
+    // Servers skip the processor, and the server version doesn't replace the client one.
+    let mut profile = profile;
+    profile["processors"].as_array_mut().unwrap().pop();
+    write_installer(
+        &installer,
+        &profile,
+        &[
+            ("version.json", version.as_bytes()),
+            ("data/server.lzma", b"server"),
+        ],
+    )
+    .await;
+
+    let id = forge::install_into(&installer, root, DownloadSource::Official, Category::Server)
+        .await
+        .unwrap();
+    assert_eq!(id, "1.20.1-forge-47.4.0-server");
+
+    tokio::fs::remove_dir_all(root).await.unwrap();
+}
+
+/// Serves the libraries of a legacy Forge installer.
+fn legacy_forge_libraries(request: Request) -> Response {
+    match request.path.as_str() {
+        "/com/example/checked/1.0/checked-1.0.jar" => Response::new(200, "checked"),
+        "/com/example/published/1.0/published-1.0.jar" => Response::new(200, "published"),
+        "/com/example/published/1.0/published-1.0.jar.sha1" => {
+            Response::new(200, format!("{:x}", Sha1::digest(b"published")))
+        }
+        _ => Response::new(404, "Not Found"),
+    }
+}
+
+#[tokio::test]
+async fn forge_install_legacy() {
+    let root = env::temp_dir().join(format!("gridcore-forge-legacy-{}", std::process::id()));
+    let _ = tokio::fs::remove_dir_all(&root).await;
+    let installer = root.join("forge-1.12.2-14.23.5.2860-installer.jar");
+    let repository = format!("{}/", common::serve(legacy_forge_libraries).await);
+
+    let mut profile = json!({
+        "install": {
+            "path": "net.minecraftforge:forge:1.12.2-14.23.5.2860",
+            "filePath": "forge-1.12.2-14.23.5.2860-universal.jar",
+            "minecraft": "1.12.2"
+        },
+        "versionInfo": {
+            "id": "1.12.2-forge-14.23.5.2860",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+            "libraries": [
+                { "name": "net.minecraftforge:forge:1.12.2-14.23.5.2860" },
+                // Only servers need it, so it isn't downloaded.
+                { "name": "lzma:lzma:0.0.1", "serverreq": true, "clientreq": false },
+                {
+                    "name": "com.example:checked:1.0",
+                    "url": repository,
+                    "checksums": [format!("{:x}", Sha1::digest(b"checked"))]
+                },
+                // The hash published along with the artifact is used instead.
+                { "name": "com.example:published:1.0", "url": repository }
+            ]
+        }
+    });
+    write_installer(
+        &installer,
+        &profile,
+        &[("forge-1.12.2-14.23.5.2860-universal.jar", b"universal")],
+    )
+    .await;
+    let root = root.to_str().unwrap();
+
+    let id = forge::install_into(&installer, root, DownloadSource::Official, Category::Client)
+        .await
+        .unwrap();
+    assert_eq!(id, "1.12.2-forge-14.23.5.2860");
+
+    // The universal jar is the library of Forge.
+    let libraries = Path::new(root).join("libraries");
+    assert_eq!(
+        tokio::fs::read(
+            libraries
+                .join("net/minecraftforge/forge/1.12.2-14.23.5.2860/forge-1.12.2-14.23.5.2860.jar")
+        )
+        .await
+        .unwrap(),
+        b"universal"
+    );
+    assert!(!libraries.join("lzma").exists());
+    assert!(
+        libraries
+            .join("com/example/checked/1.0/checked-1.0.jar")
+            .exists()
+    );
+    assert!(
+        libraries
+            .join("com/example/published/1.0/published-1.0.jar")
+            .exists()
+    );
+
+    // The parent version is filled in, since the profile doesn't declare it.
+    let data: Value = serde_json::from_slice(
+        &tokio::fs::read(
+            Path::new(root)
+                .join("versions/1.12.2-forge-14.23.5.2860/1.12.2-forge-14.23.5.2860.json"),
+        )
+        .await
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(data["inheritsFrom"], "1.12.2");
+
+    // An installer without the universal jar is refused.
+    write_installer(&Path::new(root).join("broken.jar"), &profile, &[]).await;
+    assert!(matches!(
+        forge::install_into(
+            &Path::new(root).join("broken.jar"),
+            root,
+            DownloadSource::Official,
+            Category::Client
+        )
+        .await,
+        Err(InstallError::InvalidInstaller(_))
+    ));
+
+    // A library that doesn't match its checksum is refused.
+    profile["versionInfo"]["libraries"][2]["checksums"] =
+        json!([format!("{:x}", Sha1::digest(b"corrupted"))]);
+    tokio::fs::remove_dir_all(libraries.join("com/example/checked"))
+        .await
+        .unwrap();
+    write_installer(
+        &installer,
+        &profile,
+        &[("forge-1.12.2-14.23.5.2860-universal.jar", b"universal")],
+    )
+    .await;
+    assert!(matches!(
+        forge::install_into(&installer, root, DownloadSource::Official, Category::Client).await,
+        Err(InstallError::DownloadError(_))
+    ));
+
+    tokio::fs::remove_dir_all(root).await.unwrap();
+}
+
+#[ignore = "This test case must be manually tested on local machine."]
+#[tokio::test]
+async fn install_fabric() {
+    let versions = fabric::list_loader_versions("1.21.5", DownloadSource::Official)
+        .await
+        .unwrap();
+    let loader = versions.iter().find(|v| v.stable).unwrap();
+
+    let installer = FabricInstaller::new(
+        "1.21.5",
+        &loader.version,
+        DownloadSource::Official,
+        Category::Client,
+    );
+    let id = installer.install().await.unwrap();
+
+    assert_eq!(id, format!("fabric-loader-{}-1.21.5", loader.version));
+}
+
+#[ignore = "This test case must be manually tested on local machine."]
+#[tokio::test]
+async fn install_forge() {
+    // Processors patch the vanilla jar, which must be downloaded first.
+    let installer = ForgeInstaller::new(
+        "1.20.1",
+        "47.4.0",
+        DownloadSource::Official,
+        Category::Client,
+    );
+    let id = installer.install().await.unwrap();
+
+    assert_eq!(id, "1.20.1-forge-47.4.0");
+}
+
+#[ignore = "This test case must be manually tested on local machine."]
+#[tokio::test]
+async fn install_neoforge() {
+    let versions = neoforge::list_versions("1.21.1", DownloadSource::Official)
+        .await
+        .unwrap();
+    let neoforge = versions.iter().find(|v| v.stable).unwrap();
+
+    let installer = NeoForgeInstaller::new(
+        "1.21.1",
+        &neoforge.version,
+        DownloadSource::Official,
+        Category::Client,
+    );
+    let id = installer.install().await.unwrap();
+
+    assert_eq!(id, format!("neoforge-{}", neoforge.version));
+}
+
+#[ignore = "This test case must be manually tested on local machine."]
+#[tokio::test]
+async fn install_liteloader() {
+    // LiteLoader inherits from the vanilla version, which must be downloaded first.
+    let versions = liteloader::list_versions("1.12.2", DownloadSource::Official)
+        .await
+        .unwrap();
+
+    let installer = LiteLoaderInstaller::new(
+        "1.12.2",
+        &versions[0].version,
+        "1.12.2",
+        DownloadSource::Official,
+    );
+    let id = installer.install().await.unwrap();
+
+    assert_eq!(id, format!("1.12.2-liteloader-{}", versions[0].version));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{Request, Response};
+use gridcore::auth::MinecraftProfile;
+use gridcore::auth::name::{NameAvailability, NameChangeEligibility};
+use gridcore::error_handling::AuthError;
+
+use serde_json::json;
+
+fn stand_in(request: Request) -> Response {
+    if request.method != "PUT" {
+        return Response::new(405, "");
+    }
+
+    match request.path.as_str() {
+        "/minecraft/profile/name/Jeb_" => Response::new(
+            200,
+            r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Jeb_","skins":[],"capes":[]}"#,
+        ),
+        "/minecraft/profile/name/Blocked" => Response::new(400, ""),
+        "/minecraft/profile/name/TooSoon" => Response::new(403, ""),
+        "/minecraft/profile/name/Dinnerbone" => Response::new(409, ""),
+        _ => Response::new(404, ""),
+    }
+}
+
+#[test]
+fn name_change_responses() {
+    let eligibility: NameChangeEligibility = serde_json::from_value(json!({
+        "changedAt": "2021-06-01T00:00:00Z",
+        "createdAt": "2020-12-01T00:00:00Z",
+        "nameChangeAllowed": false
+    }))
+    .unwrap();
+
+    assert!(!eligibility.name_change_allowed);
+    assert_eq!(
+        eligibility.changed_at.as_deref(),
+        Some("2021-06-01T00:00:00Z")
+    );
+
+    // A new profile has never changed its username.
+    let eligibility: NameChangeEligibility = serde_json::from_value(json!({
+        "createdAt": "2020-12-01T00:00:00Z",
+        "nameChangeAllowed": true
+    }))
+    .unwrap();
+
+    assert!(eligibility.name_change_allowed);
+    assert_eq!(eligibility.changed_at, None);
+
+    for (status, availability) in [
+        ("AVAILABLE", NameAvailability::Available),
+        ("DUPLICATE", NameAvailability::Duplicate),
+        ("NOT_ALLOWED", NameAvailability::NotAllowed),
+    ] {
+        assert_eq!(
+            serde_json::from_value::<NameAvailability>(json!(status)).unwrap(),
+            availability
+        );
+    }
+}
+
+#[tokio::test]
+async fn change_name() {
+    let profile_url = format!("{}/minecraft/profile", common::serve(stand_in).await);
+    let mut profile: MinecraftProfile = serde_json::from_value(json!({
+        "AccessToken": "minecraft",
+        "Uuid": "069a79f444e94726a5befca90e38aaf5",
+        "Username": "Notch"
+    }))
+    .unwrap();
+
+    assert!(matches!(
+        profile.change_name_with("Blocked", &profile_url).await,
+        Err(AuthError::InvalidUsername(_))
+    ));
+    assert!(matches!(
+        profile.change_name_with("TooSoon", &profile_url).await,
+        Err(AuthError::NameChangeNotAllowed)
+    ));
+    assert!(matches!(
+        profile.change_name_with("Dinnerbone", &profile_url).await,
+        Err(AuthError::NameTaken(name)) if name == "Dinnerbone"
+    ));
+    // The username is kept until it's actually changed.
+    assert_eq!(profile.username(), "Notch");
+
+    profile
+        .change_name_with("Jeb_", &profile_url)
+        .await
+        .unwrap();
+    assert_eq!(profile.username(), "Jeb_");
+}
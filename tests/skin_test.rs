@@ -0,0 +1,47 @@
+use gridcore::auth::skin::{self, SkinVariant, Textures};
+
+use serde_json::json;
+
+/// Returns the signature and the IHDR chunk of a PNG image, which is enough for validation.
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    png.extend(13u32.to_be_bytes());
+    png.extend(b"IHDR");
+    png.extend(width.to_be_bytes());
+    png.extend(height.to_be_bytes());
+    png.extend([8, 6, 0, 0, 0]);
+    png
+}
+
+#[test]
+fn validate_skin() {
+    assert_eq!(skin::validate_skin(&png_header(64, 64)).unwrap(), (64, 64));
+    assert_eq!(skin::validate_skin(&png_header(64, 32)).unwrap(), (64, 32));
+    assert!(skin::validate_skin(&png_header(128, 128)).is_err());
+    assert!(skin::validate_skin(b"GIF89a").is_err());
+}
+
+#[test]
+fn textures() {
+    let textures: Textures = serde_json::from_value(json!({
+        "id": "069a79f444e94726a5befca90e38aaf5",
+        "name": "Notch",
+        "skins": [{
+            "id": "6a6e65e5-76dd-4c3c-a625-162924514568",
+            "state": "ACTIVE",
+            "url": "http://textures.minecraft.net/texture/292009a4925b58f02c77dadc3ecef07ea4c7472f64e0fdc32ce5522489362680",
+            "variant": "CLASSIC",
+            "alias": "STEVE"
+        }],
+        "capes": [{
+            "id": "1981aad3-73b2-40b7-a3b8-0d3e4a7ba7b4",
+            "state": "INACTIVE",
+            "url": "http://textures.minecraft.net/texture/2340c0e03dd24a11b15a8b33c2a7e9e32abb2051b2481d0ba7defd635ca7a933",
+            "alias": "Migrator"
+        }]
+    }))
+    .unwrap();
+
+    assert_eq!(textures.skins[0].variant, SkinVariant::Classic);
+    assert_eq!(textures.capes[0].alias, "Migrator");
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use common::{Request, Response};
+use gridcore::auth::yggdrasil::YggdrasilClient;
+use gridcore::auth::{AccountType, MinecraftProfile};
+
+const METADATA: &str = r#"{"meta":{"serverName":"Stand-in"},"skinDomains":["localhost"]}"#;
+
+fn stand_in(request: Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => Response::new(200, "<html></html>")
+            .header("X-Authlib-Injector-API-Location", "/api/yggdrasil/"),
+        ("GET", "/api/yggdrasil") => Response::new(200, METADATA),
+        ("POST", "/api/yggdrasil/authserver/authenticate") => {
+            if request.body.contains(r#""password":"secret""#) {
+                Response::new(
+                    200,
+                    r#"{
+                        "accessToken": "token-1",
+                        "clientToken": "client",
+                        "availableProfiles": [
+                            { "id": "00000000000000000000000000000001", "name": "Alex" },
+                            { "id": "00000000000000000000000000000002", "name": "Steve" }
+                        ]
+                    }"#,
+                )
+            } else {
+                Response::new(
+                    403,
+                    r#"{"error":"ForbiddenOperationException","errorMessage":"Invalid credentials."}"#,
+                )
+            }
+        }
+        ("POST", "/api/yggdrasil/authserver/refresh") => Response::new(
+            200,
+            r#"{
+                "accessToken": "token-2",
+                "clientToken": "client",
+                "selectedProfile": { "id": "00000000000000000000000000000002", "name": "Steve" }
+            }"#,
+        ),
+        ("POST", "/api/yggdrasil/authserver/validate") => {
+            if request.body.contains(r#""accessToken":"token-2""#) {
+                Response::new(204, "")
+            } else {
+                Response::new(403, "")
+            }
+        }
+        ("POST", "/api/yggdrasil/authserver/invalidate") => Response::new(204, ""),
+        _ => Response::new(404, ""),
+    }
+}
+
+#[tokio::test]
+async fn yggdrasil_login() {
+    let homepage = common::serve(stand_in).await;
+
+    let client = YggdrasilClient::connect(&homepage).await.unwrap();
+    assert_eq!(client.api_root(), format!("{}/api/yggdrasil", homepage));
+
+    let error = client.authenticate("alex@example.com", "wrong").await;
+    assert!(
+        error
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid credentials.")
+    );
+
+    let session = client
+        .authenticate("alex@example.com", "secret")
+        .await
+        .unwrap();
+    assert_eq!(session.available_profiles.len(), 2);
+    assert!(session.selected_profile.is_none());
+
+    let session = client
+        .refresh(
+            &session.access_token,
+            &session.client_token,
+            Some(&session.available_profiles[1]),
+        )
+        .await
+        .unwrap();
+    assert!(client.validate("token-2", "client").await.unwrap());
+    assert!(!client.validate("token-1", "client").await.unwrap());
+
+    let profile = MinecraftProfile::new_yggdrasil(&client, session).unwrap();
+    assert_eq!(profile.username(), "Steve");
+    assert_eq!(profile.account_type(), AccountType::Yggdrasil);
+    assert_eq!(profile.yggdrasil_server(), Some(client.api_root()));
+    assert_eq!(profile.user_type(false), "mojang");
+
+    let args = client.injector_args(&"authlib-injector.jar");
+    assert_eq!(
+        args[0],
+        format!("-javaagent:authlib-injector.jar={}", client.api_root())
+    );
+    assert_eq!(
+        args[1],
+        "-Dauthlibinjector.yggdrasil.prefetched=eyJtZXRhIjp7InNlcnZlck5hbWUiOiJTdGFuZC1pbiJ9LCJza2luRG9tYWlucyI6WyJsb2NhbGhvc3QiXX0="
+    );
+
+    // The metadata is cached in the profile, so that the game is launched without connecting.
+    let profile: MinecraftProfile =
+        serde_json::from_str(&serde_json::to_string(&profile).unwrap()).unwrap();
+    assert_eq!(
+        profile
+            .yggdrasil_client()
+            .unwrap()
+            .injector_args(&"authlib-injector.jar"),
+        args
+    );
+
+    client.invalidate("token-2", "client").await.unwrap();
+}
+
+#[tokio::test]
+async fn yggdrasil_metadata_cache() {
+    let homepage = common::serve(stand_in).await;
+
+    // Accounts signed in before the metadata was cached only have the API root.
+    let mut profile: MinecraftProfile = serde_json::from_value(serde_json::json!({
+        "AccessToken": "token-2",
+        "Uuid": "00000000000000000000000000000002",
+        "Username": "Steve",
+        "AccountType": "Yggdrasil",
+        "YggdrasilServer": format!("{}/api/yggdrasil", homepage),
+        "ClientToken": "client"
+    }))
+    .unwrap();
+    assert!(profile.yggdrasil_client().is_none());
+
+    assert!(profile.cache_yggdrasil_metadata().await.unwrap());
+    let client = profile.yggdrasil_client().unwrap();
+    assert_eq!(client.api_root(), format!("{}/api/yggdrasil", homepage));
+
+    // The metadata is only fetched once.
+    assert!(!profile.cache_yggdrasil_metadata().await.unwrap());
+}